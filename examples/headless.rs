@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use bevy_falling_sand::{
+    sandbox::{
+        material::MaterialId,
+        sandbox::Sandbox,
+        simulation::{step_sandbox, ChunkUpdateMode},
+    },
+    SandboxConfig, SandboxPlugin,
+};
+
+const STEPS: usize = 200;
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SandboxPlugin::new(SandboxConfig {
+            headless: true,
+            update_mode: ChunkUpdateMode::Parallel,
+            ..default()
+        }))
+        .add_systems(PostStartup, pour_sand);
+
+    // Runs the startup systems so the sandbox exists
+    app.update();
+
+    let config = app.world().resource::<SandboxConfig>().clone();
+    let mut query = app.world_mut().query::<&mut Sandbox>();
+    let mut sandbox = query.single_mut(app.world_mut());
+    for _ in 0..STEPS {
+        step_sandbox(&mut sandbox, &config);
+    }
+
+    let settled = (0..sandbox.width())
+        .filter(|x| sandbox.get(*x, 0).is_some())
+        .count();
+    println!(
+        "{} sand particles are resting on the floor after {} steps",
+        settled, STEPS
+    );
+}
+
+fn pour_sand(mut sandbox_query: Query<&mut Sandbox>) {
+    let mut sandbox = sandbox_query.single_mut();
+    let center = sandbox.width() / 2;
+
+    for x in center - 10..center + 10 {
+        for y in sandbox.height() - 20..sandbox.height() {
+            let particle = sandbox.create_particle(MaterialId::SAND, x, y);
+            sandbox.set(x, y, particle);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use bevy::{prelude::*, render::camera::ScalingMode};
+use bevy_falling_sand::{spawn_sandbox, SandboxConfig, SandboxPlugin};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .insert_resource(Msaa::Off)
+        .add_plugins(SandboxPlugin::new(SandboxConfig {
+            spawn_default_sandbox: false,
+            ..default()
+        }))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>, config: Res<SandboxConfig>) {
+    commands.spawn(Camera2dBundle {
+        projection: OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: 1920.0,
+                height: 1080.0,
+            },
+            near: -1000.0,
+            ..default()
+        },
+        ..default()
+    });
+
+    let room = SandboxConfig {
+        x_chunks: 12,
+        y_chunks: 12,
+        ..config.clone()
+    };
+    spawn_sandbox(&mut commands, &mut images, &room, Vec2::new(-450.0, 0.0));
+    spawn_sandbox(
+        &mut commands,
+        &mut images,
+        &SandboxConfig { seed: 1, ..room },
+        Vec2::new(450.0, 0.0),
+    );
+}
@@ -0,0 +1,58 @@
+//! Replays a recording made with F6 in the `sandbox` example and checks that every tick
+//! ends up exactly like it did while recording.
+//!
+//! Run with `cargo run --example replay -- <path>`, which defaults to `sandbox.replay`.
+
+use std::{fs, process::ExitCode};
+
+use bevy_falling_sand::sandbox::{
+    material::MaterialRegistry, particle_definition::ParticleDefinition, replay::Replay,
+};
+
+/// The definitions the `sandbox` example loads, which replace some of the built-in materials.
+const PARTICLE_DEFINITIONS: [&str; 5] = [
+    "assets/particles/water.particle.ron",
+    "assets/particles/steam.particle.ron",
+    "assets/particles/acid.particle.ron",
+    "assets/particles/wood.particle.ron",
+    "assets/particles/grass.particle.json",
+];
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "sandbox.replay".to_string());
+
+    let replay = match fs::File::open(&path).and_then(Replay::load_from) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut materials = MaterialRegistry::default();
+    for path in PARTICLE_DEFINITIONS {
+        let text = fs::read_to_string(path).expect("Particle definitions should be readable");
+        let definition: ParticleDefinition = match path.ends_with(".json") {
+            true => serde_json::from_str(&text).expect("Particle definition should be valid"),
+            false => ron::from_str(&text).expect("Particle definition should be valid"),
+        };
+        materials.register(definition);
+    }
+
+    match replay.run(&materials) {
+        Ok(sandbox) => {
+            println!(
+                "Replayed {} ticks, final checksum {:016x}",
+                replay.ticks(),
+                sandbox.checksum()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs::File;
+
+use bevy::{prelude::*, render::camera::ScalingMode, window::PresentMode};
+use bevy_falling_sand::{
+    sandbox::{
+        history::EditHistory,
+        material::MaterialRegistry,
+        replay::InputRecorder,
+        sandbox::Sandbox,
+        save::{ReplaceSandbox, SaveCompression},
+    },
+    SandboxConfig, SandboxPlugin,
+};
+use bevy_rapier2d::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Bevy Falling Sand".into(),
+                        resolution: (1280., 720.).into(),
+                        present_mode: PresentMode::AutoVsync,
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(ImagePlugin::default_nearest()),
+        )
+        .insert_resource(Msaa::Off)
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
+        //.add_plugins(RapierDebugRenderPlugin::default())
+        .add_plugins(SandboxPlugin::new(SandboxConfig {
+            // Edit the files in `assets/particles` while the game runs to tune the particles
+            particle_definitions: [
+                "particles/water.particle.ron",
+                "particles/steam.particle.ron",
+                "particles/acid.particle.ron",
+                "particles/wood.particle.ron",
+                "particles/grass.particle.json",
+            ]
+            .map(String::from)
+            .to_vec(),
+            ..default()
+        }))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (save_and_load, record_inputs))
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle {
+        projection: OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: 1920.0,
+                height: 1080.0,
+            },
+            near: -1000.0,
+            ..default()
+        },
+        ..default()
+    });
+}
+
+const SAVE_PATH: &str = "sandbox.save";
+
+/// Saves the sandbox with F5 and loads it back with F9.
+fn save_and_load(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    materials: Res<MaterialRegistry>,
+    sandbox_query: Query<(Entity, &Sandbox)>,
+) {
+    let Ok((entity, sandbox)) = sandbox_query.get_single() else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::F5) {
+        match File::create(SAVE_PATH).and_then(|file| sandbox.save_to(file, SaveCompression::Zlib))
+        {
+            Ok(()) => info!("Saved the sandbox to {}", SAVE_PATH),
+            Err(error) => error!("Failed to save the sandbox: {}", error),
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::F9) {
+        match File::open(SAVE_PATH).and_then(|file| Sandbox::load_from(file, &materials)) {
+            Ok(sandbox) => commands.add(ReplaceSandbox { entity, sandbox }),
+            Err(error) => error!("Failed to load the sandbox: {}", error),
+        }
+    }
+}
+
+const REPLAY_PATH: &str = "sandbox.replay";
+
+/// Starts recording the placer with F6 and writes the replay when F6 is pressed again.
+/// Run `cargo run --example replay` afterwards to check that it replays the same way.
+fn record_inputs(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    config: Res<SandboxConfig>,
+    recorder: Option<Res<InputRecorder>>,
+    mut history: ResMut<EditHistory>,
+    mut sandbox_query: Query<(Entity, &mut Sandbox)>,
+) {
+    if !keyboard.just_pressed(KeyCode::F6) {
+        return;
+    }
+    let Ok((entity, mut sandbox)) = sandbox_query.get_single_mut() else {
+        return;
+    };
+
+    if recorder.is_some() {
+        commands.add(|world: &mut World| {
+            let Some(recorder) = world.remove_resource::<InputRecorder>() else {
+                return;
+            };
+            let replay = recorder.finish();
+            match File::create(REPLAY_PATH).and_then(|file| replay.save_to(file)) {
+                Ok(()) => info!("Saved {} recorded ticks to {}", replay.ticks(), REPLAY_PATH),
+                Err(error) => error!("Failed to save the replay: {}", error),
+            }
+        });
+        return;
+    }
+
+    match InputRecorder::start(entity, &mut sandbox, &mut history, config.sweep) {
+        Ok(recorder) => {
+            info!("Recording the placer");
+            commands.insert_resource(recorder);
+        }
+        Err(error) => error!("Failed to start recording: {}", error),
+    }
+}
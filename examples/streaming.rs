@@ -0,0 +1,92 @@
+use bevy::{prelude::*, render::camera::ScalingMode};
+use bevy_falling_sand::{
+    sandbox::{
+        chunk::SandboxChunk,
+        material::{MaterialId, MaterialRegistry},
+        streaming::{SandboxFocus, StreamedSandbox},
+    },
+    spawn_sandbox, SandboxConfig, SandboxPlugin,
+};
+
+const CAMERA_SPEED: f32 = 400.0;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .insert_resource(Msaa::Off)
+        .add_plugins(SandboxPlugin::new(SandboxConfig {
+            spawn_default_sandbox: false,
+            ..default()
+        }))
+        .add_systems(Startup, setup)
+        .add_systems(Update, move_camera)
+        .run();
+}
+
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>, config: Res<SandboxConfig>) {
+    commands.spawn((
+        Camera2dBundle {
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: 1920.0,
+                    height: 1080.0,
+                },
+                near: -1000.0,
+                ..default()
+            },
+            ..default()
+        },
+        SandboxFocus,
+    ));
+
+    let sandbox = spawn_sandbox(&mut commands, &mut images, &config, Vec2::ZERO);
+    commands
+        .entity(sandbox)
+        .insert(StreamedSandbox::new("regions").with_generator(generate_terrain));
+}
+
+/// Rolling stone hills with a layer of sand on top.
+fn generate_terrain(key: IVec2, chunk: &mut SandboxChunk, materials: &MaterialRegistry) {
+    for x in 0..chunk.width() {
+        let world_x = (key.x * chunk.width() as i32 + x as i32) as f32;
+        let ground = (world_x / 40.0).sin() * 12.0 - 20.0;
+
+        for y in 0..chunk.height() {
+            let world_y = (key.y * chunk.height() as i32 + y as i32) as f32;
+            let material = if world_y < ground {
+                MaterialId::STONE
+            } else if world_y < ground + 4.0 {
+                MaterialId::SAND
+            } else {
+                continue;
+            };
+
+            let particle = materials.create(material, chunk.rng());
+            chunk.set(x, y, particle);
+        }
+    }
+}
+
+fn move_camera(
+    mut camera_query: Query<&mut Transform, With<SandboxFocus>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+        direction.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowRight) {
+        direction.x += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowDown) {
+        direction.y -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowUp) {
+        direction.y += 1.0;
+    }
+
+    for mut transform in &mut camera_query {
+        transform.translation += (direction * CAMERA_SPEED * time.delta_seconds()).extend(0.0);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod sandbox;
+mod vector;
+
+pub use sandbox::{spawn_headless_sandbox, spawn_sandbox, SandboxConfig, SandboxPlugin};
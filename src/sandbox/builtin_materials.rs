@@ -0,0 +1,370 @@
+use super::{
+    material::MaterialId,
+    particle::*,
+    particle_definition::{
+        AbsorbentDefinition, BurnableDefinition, GrowableDefinition, ParticleDefinition,
+        TemperatureDefinition, TickLifeDefinition,
+    },
+};
+use bevy::utils::default;
+
+/// Returns the definitions of the built-in materials along with the id each one is registered as.
+pub fn builtin_materials() -> Vec<(MaterialId, ParticleDefinition)> {
+    vec![
+        (
+            MaterialId::SAND,
+            ParticleDefinition {
+                name: "Sand".into(),
+                color: (218, 203, 128, 255),
+                density: 1600,
+                temperature: temperature(20.0, 0.2, 1.0, Some(900.0), None, Some("Glass"), 0),
+                collision_type: CollisionType::Solid,
+                restitution: 0.2,
+                friction: 0.3,
+                absorbent: absorbent(0.0, (150, 131, 82, 255), 0.6, None, None),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::WATER,
+            ParticleDefinition {
+                name: "Water".into(),
+                health: ParticleHealth::new(1, false),
+                color: (123, 153, 200, 255),
+                movement_type: MovementType::Liquid,
+                density: 1000,
+                dispersion: 6,
+                temperature: temperature(20.0, 0.5, 4.0, Some(100.0), None, Some("Steam"), 0),
+                collision_type: CollisionType::Water,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::STONE,
+            ParticleDefinition {
+                name: "Stone".into(),
+                color: (125, 110, 110, 255),
+                movement_type: MovementType::Solid,
+                density: 2600,
+                temperature: temperature(20.0, 0.4, 1.0, None, None, None, 0),
+                growable_on: true,
+                collision_type: CollisionType::Solid,
+                restitution: 0.3,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::STEAM,
+            ParticleDefinition {
+                name: "Steam".into(),
+                health: ParticleHealth::new(100, false),
+                color: (240, 233, 201, 255),
+                movement_type: MovementType::Gas,
+                density: 0,
+                temperature: temperature(110.0, 0.1, 0.5, None, None, None, 0),
+                tick_life: tick_life((100, 120), Some("Water")),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::ACID,
+            ParticleDefinition {
+                name: "Acid".into(),
+                health: ParticleHealth::new(50, false),
+                color: (118, 195, 121, 255),
+                movement_type: MovementType::Liquid,
+                density: 1200,
+                dispersion: 4,
+                acidity: Some(5),
+                temperature: temperature(20.0, 0.3, 3.0, None, None, None, 0),
+                collision_type: CollisionType::Acid,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::WOOD,
+            ParticleDefinition {
+                name: "Wood".into(),
+                color: (101, 61, 72, 255),
+                density: 700,
+                burst_pressure: Some(45.0),
+                temperature: temperature(20.0, 0.05, 1.5, None, None, Some("Ash"), 0),
+                burnable: burnable(250.0, 1100.0, 50),
+                collision_type: CollisionType::Solid,
+                friction: 0.4,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::GLASS,
+            ParticleDefinition {
+                name: "Glass".into(),
+                health: ParticleHealth::new(50, false),
+                color: (153, 212, 230, 255),
+                movement_type: MovementType::Solid,
+                density: 2500,
+                burst_pressure: Some(35.0),
+                temperature: temperature(20.0, 0.3, 1.0, None, None, None, 0),
+                collision_type: CollisionType::Solid,
+                restitution: 0.4,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::SPARK,
+            ParticleDefinition {
+                name: "Spark".into(),
+                health: ParticleHealth::new(5, false),
+                color: (204, 146, 94, 255),
+                movement_type: MovementType::Gas,
+                density: 1,
+                temperature: temperature(1000.0, 0.5, 2.0, None, None, None, 0),
+                tick_life: tick_life((5, 10), None),
+                collision_type: CollisionType::Fire,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::EMBER,
+            ParticleDefinition {
+                name: "Ember".into(),
+                health: ParticleHealth::new(30, true),
+                color: (190, 121, 121, 255),
+                density: 300,
+                temperature: temperature(800.0, 0.3, 10.0, None, None, None, 0),
+                tick_life: tick_life((30, 45), None),
+                collision_type: CollisionType::Fire,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::SMOKE,
+            ParticleDefinition {
+                name: "Smoke".into(),
+                health: ParticleHealth::new(40, false),
+                color: (36, 22, 41, 255),
+                movement_type: MovementType::Gas,
+                density: 0,
+                temperature: temperature(60.0, 0.05, 0.5, None, None, None, 0),
+                tick_life: tick_life((40, 55), None),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::LAVA,
+            ParticleDefinition {
+                name: "Lava".into(),
+                health: ParticleHealth::new(1, false),
+                color: (178, 94, 70, 255),
+                movement_type: MovementType::Liquid,
+                density: 2500,
+                viscosity: 6,
+                temperature: temperature(1200.0, 0.3, 10.0, None, Some(700.0), Some("Igneous"), 0),
+                collision_type: CollisionType::Fire,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::OIL,
+            ParticleDefinition {
+                name: "Oil".into(),
+                health: ParticleHealth::new(50, false),
+                color: (53, 43, 64, 255),
+                movement_type: MovementType::Liquid,
+                density: 900,
+                dispersion: 3,
+                viscosity: 2,
+                temperature: temperature(20.0, 0.15, 2.0, None, None, Some("Spark"), 0),
+                burnable: burnable(200.0, 1000.0, 15),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::GUNPOWDER,
+            ParticleDefinition {
+                name: "Gunpowder".into(),
+                color: (216, 177, 161, 255),
+                density: 1200,
+                temperature: temperature(20.0, 0.2, 1.0, Some(150.0), None, None, 5),
+                collision_type: CollisionType::Solid,
+                restitution: 0.2,
+                friction: 0.5,
+                absorbent: absorbent(0.0, (140, 110, 100, 255), 0.8, None, None),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::TNT,
+            ParticleDefinition {
+                name: "Tnt".into(),
+                color: (147, 63, 69, 255),
+                movement_type: MovementType::Solid,
+                density: 1600,
+                temperature: temperature(20.0, 0.2, 1.0, Some(200.0), None, None, 15),
+                collision_type: CollisionType::Solid,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::ASH,
+            ParticleDefinition {
+                name: "Ash".into(),
+                color: (194, 181, 169, 255),
+                density: 500,
+                temperature: temperature(20.0, 0.1, 1.0, None, None, None, 0),
+                collision_type: CollisionType::Solid,
+                gravity_scale: 0.1,
+                terminal_velocity: 0.5,
+                air_drag: 0.3,
+                friction: 0.5,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::DIRT,
+            ParticleDefinition {
+                name: "Dirt".into(),
+                color: (89, 39, 39, 255),
+                movement_type: MovementType::Solid,
+                density: 1300,
+                burst_pressure: Some(40.0),
+                temperature: temperature(20.0, 0.2, 1.2, None, None, None, 0),
+                absorbent: absorbent(0.0, (62, 36, 30, 255), 0.0, Some("Mud"), None),
+                growable_on: true,
+                collision_type: CollisionType::Solid,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::GRASS,
+            ParticleDefinition {
+                name: "Grass".into(),
+                color: (80, 141, 118, 255),
+                movement_type: MovementType::Solid,
+                density: 400,
+                burst_pressure: Some(20.0),
+                temperature: temperature(20.0, 0.1, 1.0, None, None, None, 0),
+                burnable: burnable(200.0, 800.0, 8),
+                growable: Some(GrowableDefinition {
+                    energy: 2,
+                    spread_chance: 0.5,
+                    up_chance: 0.25,
+                    grow_as: "Grass".into(),
+                }),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::ALCOHOL,
+            ParticleDefinition {
+                name: "Alcohol".into(),
+                health: ParticleHealth::new(50, false),
+                color: (242, 215, 94, 255),
+                movement_type: MovementType::Liquid,
+                density: 790,
+                dispersion: 5,
+                temperature: temperature(20.0, 0.15, 2.0, None, None, Some("Spark"), 0),
+                burnable: burnable(150.0, 900.0, 15),
+                ..default()
+            },
+        ),
+        (
+            MaterialId::IGNEOUS,
+            ParticleDefinition {
+                name: "Igneous".into(),
+                color: (110, 34, 13, 255),
+                movement_type: MovementType::Solid,
+                density: 2800,
+                temperature: temperature(20.0, 0.4, 1.0, None, None, None, 0),
+                collision_type: CollisionType::Solid,
+                restitution: 0.3,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::INDESTRUCTIBLE,
+            ParticleDefinition {
+                name: "Indestructible".into(),
+                health: ParticleHealth::new(i32::MAX, false),
+                color: (210, 0, 205, 255),
+                movement_type: MovementType::Solid,
+                collision_type: CollisionType::Solid,
+                affected_by_gravity: false,
+                ..default()
+            },
+        ),
+        (
+            MaterialId::MUD,
+            ParticleDefinition {
+                name: "Mud".into(),
+                color: (99, 68, 50, 255),
+                density: 1700,
+                temperature: temperature(20.0, 0.2, 1.2, None, None, None, 0),
+                absorbent: absorbent(1.0, (62, 36, 30, 255), 0.95, None, Some("Dirt")),
+                collision_type: CollisionType::Solid,
+                friction: 0.6,
+                growable_on: true,
+                ..default()
+            },
+        ),
+    ]
+}
+
+fn temperature(
+    starting_temperature: f32,
+    conductivity: f32,
+    heat_capacity: f32,
+    critical_above: Option<f32>,
+    critical_below: Option<f32>,
+    change_on_critical: Option<&str>,
+    explosion_radius: i32,
+) -> Option<TemperatureDefinition> {
+    Some(TemperatureDefinition {
+        starting_temperature,
+        conductivity,
+        heat_capacity,
+        critical_above,
+        critical_below,
+        change_on_critical: change_on_critical.map(String::from),
+        explosion_radius,
+    })
+}
+
+fn burnable(
+    burn_temperature: f32,
+    flame_temperature: f32,
+    burn_ticks: i32,
+) -> Option<BurnableDefinition> {
+    Some(BurnableDefinition {
+        burn_temperature,
+        flame_temperature,
+        burn_ticks,
+        burn_color: (204, 146, 94, 255),
+        cooled_color: (125, 110, 110, 255),
+    })
+}
+
+fn absorbent(
+    wetness: f32,
+    wet_color: (u8, u8, u8, u8),
+    wet_friction: f32,
+    soaked_as: Option<&str>,
+    dried_as: Option<&str>,
+) -> Option<AbsorbentDefinition> {
+    Some(AbsorbentDefinition {
+        wetness,
+        soak_chance: 0.2,
+        dry_rate: 0.001,
+        wet_color,
+        wet_friction: Some(wet_friction),
+        soaked_as: soaked_as.map(String::from),
+        dried_as: dried_as.map(String::from),
+    })
+}
+
+fn tick_life(lifetime: (i32, i32), replace_on_death: Option<&str>) -> Option<TickLifeDefinition> {
+    Some(TickLifeDefinition {
+        lifetime: Some(lifetime),
+        replace_on_death: replace_on_death.map(String::from),
+    })
+}
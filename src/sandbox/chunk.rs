@@ -1,8 +1,72 @@
 use bevy::prelude::Entity;
 
-use super::particle::Particle;
+use super::{particle::Particle, rng::SandboxRng};
 
-const MAX_TICKED_BEFORE_SLEEP: u8 = 2;
+/// Inclusive bounds of the cells in a chunk that need to be revisited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl DirtyRect {
+    pub fn point(x: usize, y: usize) -> Self {
+        Self {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// Iterates the positions inside the rectangle, column by column.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (min_y, max_y) = (self.min_y, self.max_y);
+        (self.min_x..=self.max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+}
+
+/// Dirty rectangles of the current and previous tick, so marked cells stay awake for one more tick.
+#[derive(Clone, Copy, Default)]
+struct DirtyRects {
+    current: Option<DirtyRect>,
+    previous: Option<DirtyRect>,
+}
+
+impl DirtyRects {
+    fn include(&mut self, rect: DirtyRect) {
+        self.current = Some(match self.current {
+            Some(current) => current.union(rect),
+            None => rect,
+        });
+    }
+
+    fn reset(&mut self) {
+        self.previous = self.current.take();
+    }
+
+    fn get(&self) -> Option<DirtyRect> {
+        match (self.current, self.previous) {
+            (Some(current), Some(previous)) => Some(current.union(previous)),
+            (current, previous) => current.or(previous),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct SandboxChunk {
@@ -10,27 +74,39 @@ pub struct SandboxChunk {
     height: usize,
     pub local_position: (usize, usize),
     particles: Vec<Option<Particle>>,
+    /// Gas pressure of every cell, which isn't saved and builds back up from the gases.
+    pressure: Vec<f32>,
+    /// Whether any cell has pressure, so chunks without gases don't need to update it.
+    has_pressure: bool,
     pub colliders: Vec<Entity>,
-    strong_ticked: u8,
-    weak_ticked: u8,
+    rng: SandboxRng,
+    strong_dirty: DirtyRects,
+    weak_dirty: DirtyRects,
 }
 
 impl SandboxChunk {
-    pub fn new(width: usize, height: usize, index: usize, x_chunks: usize) -> Self {
-        Self {
+    pub fn new(
+        width: usize,
+        height: usize,
+        local_position: (usize, usize),
+        rng: SandboxRng,
+    ) -> Self {
+        let mut chunk = Self {
             width,
             height,
             particles: vec![None; width * height],
-            local_position: {
-                let local_x = index % x_chunks;
-                let local_y = index / x_chunks;
-
-                (local_x, local_y)
-            },
+            pressure: vec![0.0; width * height],
+            has_pressure: false,
+            local_position,
             colliders: vec![],
-            strong_ticked: MAX_TICKED_BEFORE_SLEEP,
-            weak_ticked: MAX_TICKED_BEFORE_SLEEP,
-        }
+            rng,
+            strong_dirty: DirtyRects::default(),
+            weak_dirty: DirtyRects::default(),
+        };
+        chunk.strong_tick();
+        chunk.weak_tick();
+
+        chunk
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<&Particle> {
@@ -47,28 +123,94 @@ impl SandboxChunk {
         let index = self.to_index(x, y);
         self.particles[index] = particle;
 
+        self.strong_tick_at(x, y);
+    }
+
+    pub fn pressure(&self, x: usize, y: usize) -> f32 {
+        self.pressure[self.to_index(x, y)]
+    }
+
+    pub fn set_pressure(&mut self, x: usize, y: usize, pressure: f32) {
+        let index = self.to_index(x, y);
+        self.pressure[index] = pressure;
+        self.has_pressure |= pressure != 0.0;
+    }
+
+    /// Replaces the pressure of every cell. The length must match the chunk's size.
+    pub fn set_pressures(&mut self, pressure: Vec<f32>) {
+        assert_eq!(
+            pressure.len(),
+            self.width * self.height,
+            "Pressure doesn't fit the chunk"
+        );
+        self.has_pressure = pressure.iter().any(|&pressure| pressure != 0.0);
+        self.pressure = pressure;
+    }
+
+    pub fn has_pressure(&self) -> bool {
+        self.has_pressure
+    }
+
+    pub fn particles(&self) -> &[Option<Particle>] {
+        &self.particles
+    }
+
+    /// Replaces every particle in the chunk. The length must match the chunk's size.
+    pub fn load_particles(&mut self, particles: Vec<Option<Particle>>) {
+        assert_eq!(
+            particles.len(),
+            self.width * self.height,
+            "Loaded particles don't fit the chunk"
+        );
+        self.particles = particles;
+
         self.strong_tick();
     }
 
     pub fn reset_ticked(&mut self) {
-        self.strong_ticked = self.strong_ticked.saturating_sub(1);
-        self.weak_ticked = self.weak_ticked.saturating_sub(1);
+        self.strong_dirty.reset();
+        self.weak_dirty.reset();
     }
 
+    /// Marks the whole chunk as changed.
     pub fn strong_tick(&mut self) {
-        self.strong_ticked = MAX_TICKED_BEFORE_SLEEP;
+        self.strong_dirty.include(self.full_rect());
     }
 
+    /// Marks the whole chunk as needing to be simulated.
     pub fn weak_tick(&mut self) {
-        self.weak_ticked = MAX_TICKED_BEFORE_SLEEP;
+        self.weak_dirty.include(self.full_rect());
+    }
+
+    /// Marks a cell as changed, so it is simulated, rendered and has its colliders rebuilt.
+    pub fn strong_tick_at(&mut self, x: usize, y: usize) {
+        self.strong_dirty.include(DirtyRect::point(x, y));
+    }
+
+    /// Marks a cell as needing to be simulated without it having visibly changed.
+    pub fn weak_tick_at(&mut self, x: usize, y: usize) {
+        self.weak_dirty.include(DirtyRect::point(x, y));
     }
 
     pub fn is_strong_ticked(&self) -> bool {
-        self.strong_ticked > 0
+        self.strong_dirty.get().is_some()
     }
 
     pub fn is_weak_ticked(&self) -> bool {
-        self.weak_ticked > 0
+        self.weak_dirty.get().is_some()
+    }
+
+    /// Returns the cells that changed during this or the previous tick.
+    pub fn strong_dirty_rect(&self) -> Option<DirtyRect> {
+        self.strong_dirty.get()
+    }
+
+    /// Returns the cells that need to be simulated.
+    pub fn dirty_rect(&self) -> Option<DirtyRect> {
+        match (self.strong_dirty.get(), self.weak_dirty.get()) {
+            (Some(strong), Some(weak)) => Some(strong.union(weak)),
+            (strong, weak) => strong.or(weak),
+        }
     }
 
     pub fn mark_updated(&mut self, x: usize, y: usize) {
@@ -79,12 +221,24 @@ impl SandboxChunk {
         }
     }
 
+    /// Clears the updated flag of the particles moved this tick, which are all inside the strong rectangle.
     pub fn reset_updated(&mut self) {
-        for particle in self.particles.iter_mut().filter_map(|x| x.as_mut()) {
-            particle.updated = false;
+        let Some(rect) = self.strong_dirty.get() else {
+            return;
+        };
+
+        for (x, y) in rect.iter() {
+            let index = self.to_index(x, y);
+            if let Some(particle) = self.particles[index].as_mut() {
+                particle.updated = false;
+            }
         }
     }
 
+    pub fn rng(&mut self) -> &mut SandboxRng {
+        &mut self.rng
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -93,6 +247,15 @@ impl SandboxChunk {
         self.height
     }
 
+    fn full_rect(&self) -> DirtyRect {
+        DirtyRect {
+            min_x: 0,
+            min_y: 0,
+            max_x: self.width - 1,
+            max_y: self.height - 1,
+        }
+    }
+
     fn to_index(&self, x: usize, y: usize) -> usize {
         (y * self.width) + x
     }
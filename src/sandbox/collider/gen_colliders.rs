@@ -1,25 +1,45 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::sandbox::{particle::CollisionType, sandbox::Sandbox};
+use crate::sandbox::{particle::CollisionType, sandbox::Sandbox, SandboxConfig};
 
 use super::{utils::*, ColliderStorage};
 
 pub fn generate_sandbox_colliders(
     mut commands: Commands,
-    sandbox: Query<&mut Sandbox>,
-    mut storage: ResMut<ColliderStorage>,
+    mut sandbox_query: Query<(&Sandbox, &mut ColliderStorage, Option<&Transform>)>,
+    config: Res<SandboxConfig>,
+) {
+    for (sandbox, mut storage, transform) in &mut sandbox_query {
+        // Headless sandboxes have no transform, so they sit at the origin
+        let (offset, scale) = match transform {
+            Some(transform) => (transform.translation.truncate(), transform.scale.truncate()),
+            None => (Vec2::ZERO, Vec2::splat(config.pixel_scale)),
+        };
+
+        generate_colliders(&mut commands, sandbox, &mut storage, offset, scale);
+    }
+}
+
+fn generate_colliders(
+    commands: &mut Commands,
+    sandbox: &Sandbox,
+    storage: &mut ColliderStorage,
+    offset: Vec2,
+    scale: Vec2,
 ) {
-    let sandbox = sandbox.single();
     let width = sandbox.width();
     let height = sandbox.height();
 
     let chunks = sandbox.get_all_chunks();
     for (i, chunk) in chunks.iter().enumerate() {
+        // Colliders are polylines traced through the whole chunk, which can't be rebuilt for
+        // part of it without splitting them, so a chunk rebuilds all of them once anything in it
+        // changed
         if !chunk.is_strong_ticked() {
             continue;
         }
-        despawn_old_colliders(&mut storage, i, &mut commands);
+        despawn_old_colliders(storage, i, commands);
 
         let low = local_to_world(chunk, Vec2::ZERO);
         let high = local_to_world(
@@ -47,7 +67,8 @@ pub fn generate_sandbox_colliders(
                     .map(|pos| {
                         ((pos - Vec2::new(width as f32 / 2.0, height as f32 / 2.0))
                             + Vec2::new(0.5, 0.5))
-                            * Vec2::new(8.0, 8.0)
+                            * scale
+                            + offset
                     })
                     .collect();
 
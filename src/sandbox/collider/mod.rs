@@ -4,8 +4,6 @@ use bevy::prelude::*;
 
 use self::gen_colliders::generate_sandbox_colliders;
 
-use super::{SANDBOX_X_CHUNKS, SANDBOX_Y_CHUNKS};
-
 pub mod gen_colliders;
 mod utils;
 
@@ -13,20 +11,20 @@ pub struct SandboxColliderPlugin;
 
 impl Plugin for SandboxColliderPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ColliderStorage::default())
-            .add_systems(Update, generate_sandbox_colliders);
+        app.add_systems(Update, generate_sandbox_colliders);
     }
 }
 
-#[derive(Resource)]
+/// Colliders generated for each chunk of the [`Sandbox`](crate::sandbox::sandbox::Sandbox) on the same entity.
+#[derive(Component)]
 pub struct ColliderStorage {
     pub colliders: Vec<Option<Vec<Entity>>>,
 }
 
-impl Default for ColliderStorage {
-    fn default() -> Self {
+impl ColliderStorage {
+    pub fn new(chunk_count: usize) -> Self {
         Self {
-            colliders: vec![None; SANDBOX_X_CHUNKS * SANDBOX_Y_CHUNKS],
+            colliders: vec![None; chunk_count],
         }
     }
 }
@@ -1,4 +1,4 @@
-use bevy::prelude::{Commands, ResMut, Vec2};
+use bevy::prelude::{Commands, Vec2};
 
 use crate::sandbox::{chunk::SandboxChunk, particle::CollisionType, sandbox::Sandbox};
 
@@ -84,11 +84,7 @@ pub fn get_at(sandbox: &Sandbox, x: i32, y: i32, collision_type: CollisionType)
     }
 }
 
-pub fn despawn_old_colliders(
-    storage: &mut ResMut<ColliderStorage>,
-    i: usize,
-    commands: &mut Commands,
-) {
+pub fn despawn_old_colliders(storage: &mut ColliderStorage, i: usize, commands: &mut Commands) {
     if let Some(colliders) = &storage.colliders[i] {
         for entity in colliders {
             commands.entity(*entity).despawn();
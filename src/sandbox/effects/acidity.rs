@@ -36,11 +36,11 @@ pub fn tick_acidity(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
                 sandbox.set(neighbor_x, neighbor_y, None);
             }
 
-            sandbox.get_chunk_mut(neighbor_x, neighbor_y).weak_tick();
+            sandbox.weak_tick(neighbor_x, neighbor_y);
         }
     }
 
-    sandbox.get_chunk_mut(x, y).weak_tick();
+    sandbox.weak_tick(x, y);
 
     let acid_health = &mut sandbox.get_mut(x, y).unwrap().health;
     acid_health.amount -= acid_ticks;
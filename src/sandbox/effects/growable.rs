@@ -1,9 +1,9 @@
 use rand::prelude::*;
 
-use crate::sandbox::{particle_types::get_particle, sandbox::Sandbox};
+use crate::sandbox::sandbox::Sandbox;
 
 pub fn tick_growable(x: usize, y: usize, sandbox: &mut Sandbox) {
-    sandbox.get_chunk_mut(x, y).weak_tick();
+    sandbox.weak_tick(x, y);
 
     if try_spread(x, y, sandbox) {
         return;
@@ -19,7 +19,7 @@ fn try_spread(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
         .growable
     {
         Some(growable) => {
-            if !thread_rng().gen_bool(growable.spread_chance) {
+            if !sandbox.rng(x, y).gen_bool(growable.spread_chance) {
                 return false;
             }
 
@@ -34,7 +34,7 @@ fn try_spread(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
         (x, y.overflowing_sub(1).0),
         (x, y + 1),
     ];
-    search_directions.shuffle(&mut thread_rng());
+    search_directions.shuffle(sandbox.rng(x, y));
 
     for (neighbor_x, neighbor_y) in search_directions {
         if let Some(particle) = sandbox.checked_get(neighbor_x, neighbor_y) {
@@ -42,7 +42,9 @@ fn try_spread(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
                 continue;
             }
 
-            let mut new_particle = get_particle(grow_as);
+            let Some(mut new_particle) = sandbox.create_particle(grow_as, x, y) else {
+                return false;
+            };
             new_particle.updated = true;
             sandbox.set(neighbor_x, neighbor_y, Some(new_particle));
             return true;
@@ -59,7 +61,7 @@ fn try_upwards_growth(x: usize, y: usize, sandbox: &mut Sandbox) {
         .growable
     {
         Some(growable) => {
-            if !thread_rng().gen_bool(growable.spread_chance) || !growable.can_sprout {
+            if !sandbox.rng(x, y).gen_bool(growable.spread_chance) || !growable.can_sprout {
                 return;
             }
 
@@ -74,7 +76,7 @@ fn try_upwards_growth(x: usize, y: usize, sandbox: &mut Sandbox) {
         (x, y + 1),
         (x, y.overflowing_sub(1).0),
     ];
-    search_directions.shuffle(&mut thread_rng());
+    search_directions.shuffle(sandbox.rng(x, y));
 
     for (neighbor_x, neighbor_y) in search_directions {
         if sandbox.checked_get(neighbor_x, neighbor_y).is_some()
@@ -83,7 +85,9 @@ fn try_upwards_growth(x: usize, y: usize, sandbox: &mut Sandbox) {
             continue;
         }
 
-        let mut new_particle = get_particle(growable.grow_as);
+        let Some(mut new_particle) = sandbox.create_particle(growable.grow_as, x, y) else {
+            return;
+        };
         new_particle.updated = true;
         sandbox.set(neighbor_x, neighbor_y, Some(new_particle));
     }
@@ -1,5 +1,7 @@
 pub(crate) mod acidity;
 pub(crate) mod growable;
 pub(crate) mod movement;
+pub(crate) mod reaction;
 pub(crate) mod temperature;
 pub(crate) mod tick_life;
+pub(crate) mod wetness;
@@ -1,10 +1,33 @@
-use bevy::prelude::default;
-use rand::*;
+use bevy::prelude::{default, Vec2};
+use rand::Rng;
 
+use crate::sandbox::flying::try_launch;
 use crate::sandbox::particle::*;
+use crate::sandbox::pressure::pressure_push;
 use crate::sandbox::sandbox::Sandbox;
 use crate::vector::*;
 
+/// Velocity gravity adds every tick, in cells per tick.
+const GRAVITY: f32 = 1.0;
+
+/// Slowest a particle bounces or gets knocked away in cells per tick, so resting particles
+/// settle instead of jittering.
+const MIN_BOUNCE_SPEED: f32 = 1.0;
+
+/// Share of the momentum a particle that can't move any further passes on sideways.
+const DEFLECTION: f32 = 0.5;
+
+const NEIGHBORS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+];
+
 #[derive(Default)]
 struct StepData {
     new_x: i32,
@@ -14,14 +37,53 @@ struct StepData {
     other_x: i32,
     other_y: i32,
     swap: bool,
+    /// Whether the particle flows sideways instead of following its velocity.
+    flowed: bool,
+    /// Whether the particle was denser than one in its way but didn't sink through it this tick.
+    sinking: bool,
 }
 
 pub fn tick_movement(x: usize, y: usize, sandbox: &mut Sandbox) {
     apply_gravity(x, y, sandbox);
+    apply_pressure(x, y, sandbox);
 
-    let step_data = get_step_data(x as i32, y as i32, sandbox);
+    let Some((step_x, step_y)) = take_step(x, y, sandbox) else {
+        sandbox.weak_tick(x, y);
+        return;
+    };
+    if try_launch(x, y, (step_x.signum(), step_y.signum()), sandbox) {
+        return;
+    }
+
+    let slides = slides(x, y, sandbox);
+    let clockwise_priority = sandbox.rng(x, y).gen_bool(0.5);
+    let step_data = get_step_data(
+        x as i32,
+        y as i32,
+        step_x,
+        step_y,
+        slides,
+        clockwise_priority,
+        sandbox,
+    );
+
+    // Viscous particles stay awake until they get to flow
+    let viscosity = sandbox.get(x, y).unwrap().viscosity;
+    if step_data.flowed && viscosity > 1 && !sandbox.rng(x, y).gen_ratio(1, viscosity) {
+        sandbox.get_mut(x, y).unwrap().velocity = Velocity::default();
+        sandbox.weak_tick(x, y);
+        return;
+    }
+
+    if step_data.moved || step_data.swap {
+        sandbox.get_mut(x, y).unwrap().resting = false;
+        if step_data.new_x != x as i32 {
+            knock_loose(x, y, sandbox);
+        }
+    }
 
     if step_data.swap {
+        splash(x, y, &step_data, sandbox);
         let current_particle = sandbox.get(x, y).unwrap();
         sandbox.set(
             step_data.new_x as usize,
@@ -44,7 +106,18 @@ pub fn tick_movement(x: usize, y: usize, sandbox: &mut Sandbox) {
     }
 
     if !step_data.moved {
-        sandbox.get_mut(x, y).unwrap().velocity = Velocity::new(0, 0);
+        // Keeps trying until it sinks through
+        if step_data.sinking {
+            sandbox.weak_tick(x, y);
+        }
+        if float_up(x, y, sandbox) {
+            return;
+        }
+
+        let particle = sandbox.get_mut(x, y).unwrap();
+        particle.resting =
+            particle.movement_type == MovementType::Powder && particle.current_friction() > 0.0;
+        collide(x, y, (step_x, step_y), &step_data, sandbox);
         return;
     }
 
@@ -52,55 +125,345 @@ pub fn tick_movement(x: usize, y: usize, sandbox: &mut Sandbox) {
     sandbox.mark_updated(step_data.new_x as usize, step_data.new_y as usize);
 }
 
+/// Lets a particle stuck under a denser liquid rise through it as the liquid sinks into its
+/// place, returning whether it rose.
+fn float_up(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let particle = *sandbox.get(x, y).unwrap();
+    let Some(above) = sandbox.checked_get(x, y + 1).copied() else {
+        return false;
+    };
+    if particle.movement_type == MovementType::Solid
+        || above.movement_type != MovementType::Liquid
+        || above.density.0 <= particle.density.0
+        || above.updated
+    {
+        return false;
+    }
+
+    // Keeps trying until it floats up
+    sandbox.weak_tick(x, y);
+    if !sandbox.rng(x, y).gen_bool(sink_chance(&above, &particle)) {
+        return false;
+    }
+
+    sandbox.get_mut(x, y).unwrap().velocity = Velocity::default();
+    sandbox.swap(x, y, x, y + 1);
+    sandbox.mark_updated(x, y + 1);
+    true
+}
+
+/// Whether the particle may slide down the sides of what it lands on, which powders held by
+/// their friction don't.
+fn slides(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let particle = *sandbox.get(x, y).unwrap();
+    let friction = particle.current_friction();
+    if particle.movement_type != MovementType::Powder || friction == 0.0 {
+        return true;
+    }
+
+    !particle.resting && !sandbox.rng(x, y).gen_bool(friction as f64)
+}
+
+/// Lets a powder sliding sideways knock the resting powders around the cell it left loose, each
+/// depending on its friction.
+fn knock_loose(x: usize, y: usize, sandbox: &mut Sandbox) {
+    if sandbox.get(x, y).unwrap().movement_type != MovementType::Powder {
+        return;
+    }
+
+    for (offset_x, offset_y) in NEIGHBORS {
+        let (neighbor_x, neighbor_y) = (x as i32 + offset_x, y as i32 + offset_y);
+        let Some(&neighbor) = sandbox.checked_get_i32(neighbor_x, neighbor_y) else {
+            continue;
+        };
+        if !neighbor.resting {
+            continue;
+        }
+
+        let (neighbor_x, neighbor_y) = (neighbor_x as usize, neighbor_y as usize);
+        if sandbox
+            .rng(neighbor_x, neighbor_y)
+            .gen_bool(1.0 - neighbor.current_friction() as f64)
+        {
+            sandbox.get_mut(neighbor_x, neighbor_y).unwrap().resting = false;
+            sandbox.weak_tick(neighbor_x, neighbor_y);
+        }
+    }
+}
+
 fn apply_gravity(x: usize, y: usize, sandbox: &mut Sandbox) {
     let particle = sandbox
         .get_mut(x, y)
         .expect("Simulation should have skipped this particle");
+    accelerate(particle);
+}
+
+/// Speeds the particle up by its gravity, then slows it down by its air drag and terminal
+/// velocity.
+pub(crate) fn accelerate(particle: &mut Particle) {
     if !particle.affected_by_gravity {
         return;
     }
 
+    let gravity = GRAVITY * particle.gravity_scale;
     match particle.movement_type {
-        MovementType::Powder | MovementType::Liquid => particle.velocity.y -= 1,
-        MovementType::Gas => particle.velocity.y += 1,
+        MovementType::Powder | MovementType::Liquid => particle.velocity.y -= gravity,
+        MovementType::Gas => particle.velocity.y += gravity,
         MovementType::Solid => return,
     }
-    particle.velocity.zero_out();
+
+    let velocity = &mut particle.velocity;
+    velocity.x *= 1.0 - particle.air_drag;
+    velocity.y *= 1.0 - particle.air_drag;
+
+    let speed = velocity.speed();
+    if speed > particle.terminal_velocity {
+        velocity.x *= particle.terminal_velocity / speed;
+        velocity.y *= particle.terminal_velocity / speed;
+    }
 }
 
-fn get_step_data(x: i32, y: i32, sandbox: &Sandbox) -> StepData {
-    let particle = sandbox
+fn apply_pressure(x: usize, y: usize, sandbox: &mut Sandbox) {
+    let (push_x, push_y) = pressure_push(x, y, sandbox);
+    if (push_x, push_y) == (0.0, 0.0) {
+        return;
+    }
+
+    let particle = sandbox.get_mut(x, y).unwrap();
+    particle.velocity.x += push_x;
+    particle.velocity.y += push_y;
+    particle.thrown = true;
+}
+
+/// Returns the whole cells the particle moves this tick, or `None` while it slowly crosses its
+/// own cell towards an empty one.
+///
+/// Particles slower than a cell per tick step towards the cell they're heading to once it's taken,
+/// so they still settle, swap and flow. Faster ones move at most a chunk per tick, since that's
+/// as far as the view of their tile reaches.
+fn take_step(x: usize, y: usize, sandbox: &mut Sandbox) -> Option<(i32, i32)> {
+    let (max_x, max_y) = (sandbox.chunk_width() as i32, sandbox.chunk_height() as i32);
+    let velocity = &mut sandbox.get_mut(x, y).unwrap().velocity;
+    let (step_x, step_y) = velocity.step();
+    let step = (step_x.clamp(-max_x, max_x), step_y.clamp(-max_y, max_y));
+    let heading = (direction(velocity.x), direction(velocity.y));
+    if step != (0, 0) || heading == (0, 0) {
+        return Some(step);
+    }
+
+    let (next_x, next_y) = (x as i32 + heading.0, y as i32 + heading.1);
+    if sandbox.out_of_bounds_i32(next_x, next_y)
+        || sandbox.checked_get_i32(next_x, next_y).is_some()
+    {
+        return Some(heading);
+    }
+
+    None
+}
+
+fn direction(speed: f32) -> i32 {
+    if speed > 0.0 {
+        1
+    } else if speed < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Shares the momentum of a particle that can't move with the particle it ran into, weighted by
+/// their densities, and bounces it back by their restitution.
+///
+/// Walls and solids don't move, while loose particles that are blocked themselves pass the
+/// momentum on sideways, which makes piles scatter and liquids splash.
+fn collide(x: usize, y: usize, step: (i32, i32), step_data: &StepData, sandbox: &mut Sandbox) {
+    let particle = *sandbox.get(x, y).unwrap();
+    let normal = Vec2::new(step.0 as f32, step.1 as f32).normalize_or_zero();
+    let velocity = vector(particle.velocity);
+    let other = step_data.other_particle;
+    let other_velocity = other.map_or(Vec2::ZERO, |other| vector(other.velocity));
+
+    let closing_speed = (velocity - other_velocity).dot(normal);
+    let mut new_velocity = Velocity::default();
+    if closing_speed > 0.0 {
+        let restitution = other.map_or(particle.restitution, |other| {
+            (particle.restitution + other.restitution) / 2.0
+        });
+        let movable = other.filter(|other| other.movement_type != MovementType::Solid);
+        let other_mass = movable.map_or(f32::INFINITY, |other| mass(&other));
+        let mass = mass(&particle);
+        let impulse = (1.0 + restitution) * closing_speed / (1.0 / mass + 1.0 / other_mass);
+
+        // Particles lose their speed along the surface they ran into, like they always have
+        let bounce = velocity.dot(normal) - impulse / mass;
+        if bounce.abs() >= MIN_BOUNCE_SPEED {
+            new_velocity = Velocity::new(normal.x * bounce, normal.y * bounce);
+        }
+
+        if movable.is_some() {
+            let knock = impulse / other_mass;
+            knock_away(step_data, normal, knock, sandbox);
+        }
+    }
+
+    sandbox.get_mut(x, y).unwrap().velocity = new_velocity;
+}
+
+/// Adds the velocity a collision knocks a particle away with, sending it to a random side when
+/// it can't move along the collision itself.
+fn knock_away(step_data: &StepData, normal: Vec2, knock: f32, sandbox: &mut Sandbox) {
+    let (other_x, other_y) = (step_data.other_x, step_data.other_y);
+    let (behind_x, behind_y) = (
+        other_x + normal.x.round() as i32,
+        other_y + normal.y.round() as i32,
+    );
+    let blocked = sandbox.out_of_bounds_i32(behind_x, behind_y)
+        || sandbox.checked_get_i32(behind_x, behind_y).is_some();
+
+    // Friction holds powders in place when they get knocked sideways
+    let other_friction = sandbox
+        .get(other_x as usize, other_y as usize)
+        .unwrap()
+        .current_friction();
+    let knock = if blocked {
+        let side = random_side(other_x, other_y, sandbox);
+        normal.perp() * side * knock * DEFLECTION * (1.0 - other_friction)
+    } else {
+        normal * knock
+    };
+    if knock.length() < MIN_BOUNCE_SPEED {
+        return;
+    }
+
+    let other = sandbox.get_mut(other_x as usize, other_y as usize).unwrap();
+    other.velocity.x += knock.x;
+    other.velocity.y += knock.y;
+    other.resting = false;
+    other.thrown = true;
+    sandbox.weak_tick(other_x as usize, other_y as usize);
+}
+
+/// Slows a particle down as it pushes through a lighter one, which gets thrown back and to a
+/// random side like a splash.
+fn splash(x: usize, y: usize, step_data: &StepData, sandbox: &mut Sandbox) {
+    let particle = *sandbox.get(x, y).unwrap();
+    let other = step_data
+        .other_particle
+        .expect("Particles only swap with another particle");
+    let (other_x, other_y) = (step_data.other_x, step_data.other_y);
+    let normal =
+        Vec2::new((other_x - x as i32) as f32, (other_y - y as i32) as f32).normalize_or_zero();
+
+    let closing_speed = (vector(particle.velocity) - vector(other.velocity)).dot(normal);
+    if closing_speed <= 0.0 {
+        return;
+    }
+
+    // Both move on together along the collision
+    let (mass, other_mass) = (mass(&particle), mass(&other));
+    let share = closing_speed / (mass + other_mass);
+    let velocity = &mut sandbox.get_mut(x, y).unwrap().velocity;
+    velocity.x -= normal.x * share * other_mass;
+    velocity.y -= normal.y * share * other_mass;
+
+    let thrown = share * mass * DEFLECTION;
+    if thrown < MIN_BOUNCE_SPEED {
+        return;
+    }
+
+    let direction = (normal.perp() * random_side(other_x, other_y, sandbox) - normal).normalize();
+    let other = sandbox.get_mut(other_x as usize, other_y as usize).unwrap();
+    other.velocity.x += direction.x * thrown;
+    other.velocity.y += direction.y * thrown;
+    other.thrown = true;
+}
+
+/// Returns 1 or -1 to pick a side to send a particle to.
+fn random_side(x: i32, y: i32, sandbox: &mut Sandbox) -> f32 {
+    match sandbox.rng(x as usize, y as usize).gen_bool(0.5) {
+        true => 1.0,
+        false => -1.0,
+    }
+}
+
+/// Even the lightest gases have some mass.
+fn mass(particle: &Particle) -> f32 {
+    particle.density.0 as f32 + 1.0
+}
+
+fn vector(velocity: Velocity) -> Vec2 {
+    Vec2::new(velocity.x, velocity.y)
+}
+
+fn get_step_data(
+    x: i32,
+    y: i32,
+    step_x: i32,
+    step_y: i32,
+    slides: bool,
+    clockwise_priority: bool,
+    sandbox: &mut Sandbox,
+) -> StepData {
+    let particle = *sandbox
         .get(x as usize, y as usize)
         .expect("Simulation should have skipped this particle");
 
     let rotation_type_amount = match particle.movement_type {
+        MovementType::Powder if !slides => 1,
         MovementType::Powder => 3,
         MovementType::Liquid | MovementType::Gas => 5,
         MovementType::Solid => return StepData::default(),
     };
 
-    let clockwise_prioity = thread_rng().gen_bool(0.5);
-    let mut movement_rotations = match clockwise_prioity {
+    let mut movement_rotations = match clockwise_priority {
         true => vec![0, 1, 2, 3, 4],
-        false => vec![0, 2, 1, 4, 2],
+        false => vec![0, 2, 1, 4, 3],
     };
     movement_rotations.truncate(rotation_type_amount);
 
+    let mut hit = StepData::default();
+    let mut sinking = false;
     for i in movement_rotations {
         let mut step_data =
-            line_with_rotation(x, y, particle.velocity.x, particle.velocity.y, sandbox, i);
+            line_with_rotation(x, y, step_x, step_y, particle.dispersion as i32, sandbox, i);
+        step_data.flowed = i >= 3;
 
         if step_data.moved {
             return step_data;
         } else if let Some(entity) = step_data.other_particle {
-            if particle.density.0 > entity.density.0 {
-                step_data.swap = true;
-                return step_data;
+            let lighter = entity.movement_type != MovementType::Solid
+                && entity.density.0 < particle.density.0;
+            if lighter {
+                let chance = sink_chance(&particle, &entity);
+                if sandbox.rng(x as usize, y as usize).gen_bool(chance) {
+                    step_data.swap = true;
+                    return step_data;
+                }
+                sinking = true;
             }
         }
+
+        // The particle collides with whatever blocks it head-on
+        if i == 0 {
+            hit = StepData {
+                other_particle: step_data.other_particle,
+                other_x: step_data.other_x,
+                other_y: step_data.other_y,
+                ..default()
+            };
+        }
     }
 
-    StepData::default()
+    hit.sinking = sinking;
+    hit
+}
+
+/// Chance for a particle to swap places with a lighter one in its way, which grows with the
+/// difference in density like buoyancy does. Dense particles sink through gases right away,
+/// while liquids of similar densities take a while to settle into layers.
+fn sink_chance(particle: &Particle, lighter: &Particle) -> f64 {
+    let (density, lighter_density) = (particle.density.0 as f64, lighter.density.0 as f64);
+    (density - lighter_density) / density
 }
 
 fn line(mut x1: i32, mut y1: i32, x2: i32, y2: i32, sandbox: &Sandbox) -> StepData {
@@ -201,6 +564,7 @@ fn line_with_rotation(
     start_y: i32,
     w: i32,
     h: i32,
+    dispersion: i32,
     matrix: &Sandbox,
     rotate_type: u32,
 ) -> StepData {
@@ -208,8 +572,8 @@ fn line_with_rotation(
         0 => (w, h),
         1 => rotate_45_clockwise(w, h),
         2 => rotate_45_counterclockwise(w, h),
-        3 => rotate_90_clockwise_normalized(w, h),
-        4 => rotate_90_counterclockwise_normalized(w, h),
+        3 => scale(rotate_90_clockwise_normalized(w, h), dispersion),
+        4 => scale(rotate_90_counterclockwise_normalized(w, h), dispersion),
         _ => panic!("{} is not a rotation type. Use numbers within range 0..=4.", rotate_type),
     };
     let desired_position = (start_x + velocity.0, start_y + velocity.1);
@@ -222,3 +586,7 @@ fn line_with_rotation(
         matrix,
     )
 }
+
+fn scale((x, y): (i32, i32), factor: i32) -> (i32, i32) {
+    (x * factor, y * factor)
+}
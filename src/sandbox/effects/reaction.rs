@@ -0,0 +1,95 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::sandbox::{
+    material::{MaterialId, Product},
+    sandbox::Sandbox,
+};
+
+/// Returns true if the current particle was replaced or removed during the tick
+pub fn tick_reactions(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let material = sandbox
+        .get(x, y)
+        .expect("Simulation shouldn't have let it get this far")
+        .material;
+    if !sandbox.materials().is_reactive(material) {
+        return false;
+    }
+
+    let mut search_directions = [
+        (x.overflowing_sub(1).0, y),
+        (x + 1, y),
+        (x, y.overflowing_sub(1).0),
+        (x, y + 1),
+    ];
+    search_directions.shuffle(sandbox.rng(x, y));
+
+    for (neighbor_x, neighbor_y) in search_directions {
+        let Some(other) = sandbox.checked_get(neighbor_x, neighbor_y) else {
+            continue;
+        };
+        let Some(reaction) = sandbox
+            .materials()
+            .reaction(material, other.material)
+            .copied()
+        else {
+            continue;
+        };
+
+        // Keeps trying for as long as the particles touch
+        sandbox.weak_tick(x, y);
+        if !sandbox.rng(x, y).gen_bool(reaction.chance) {
+            continue;
+        }
+
+        produce(reaction.other_becomes, neighbor_x, neighbor_y, sandbox);
+        let replaced = produce(reaction.becomes, x, y, sandbox);
+        if let Some(byproduct) = reaction.byproduct {
+            place_byproduct(byproduct, x, y, sandbox);
+        }
+
+        return replaced;
+    }
+
+    false
+}
+
+/// Returns true if the particle was replaced or removed
+fn produce(product: Product<MaterialId>, x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let particle = match product {
+        Product::Unchanged => return false,
+        Product::Empty => None,
+        Product::Material(material) => {
+            sandbox.create_particle(material, x, y).map(|mut particle| {
+                particle.updated = true;
+                particle
+            })
+        }
+    };
+
+    sandbox.set(x, y, particle);
+    true
+}
+
+/// Puts the byproduct where the particle was, or in an empty cell next to it.
+fn place_byproduct(byproduct: MaterialId, x: usize, y: usize, sandbox: &mut Sandbox) {
+    let mut search_directions = [
+        (x.overflowing_sub(1).0, y),
+        (x + 1, y),
+        (x, y.overflowing_sub(1).0),
+        (x, y + 1),
+    ];
+    search_directions.shuffle(sandbox.rng(x, y));
+
+    let empty = std::iter::once((x, y))
+        .chain(search_directions)
+        .find(|&(x, y)| !sandbox.out_of_bounds_usize(x, y) && sandbox.get(x, y).is_none());
+    let Some((empty_x, empty_y)) = empty else {
+        return;
+    };
+
+    let Some(mut particle) = sandbox.create_particle(byproduct, x, y) else {
+        return;
+    };
+    particle.updated = true;
+    sandbox.set(empty_x, empty_y, Some(particle));
+}
@@ -1,94 +1,171 @@
-use bevy::prelude::Vec2;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-use crate::sandbox::{particle::*, particle_types::*, sandbox::Sandbox};
+use super::wetness::boil;
+use crate::sandbox::{
+    material::MaterialId, particle::*, pressure::EXPLOSION_PRESSURE, sandbox::Sandbox,
+};
+
+/// Degrees a burning particle heats up by every tick, until it reaches its flame temperature.
+const BURN_HEAT: f32 = 150.0;
+
+/// Difference in degrees below which a particle counts as level with a neighbour or the air, so
+/// poorly conducting particles don't fall asleep while they're still far from the ambient
+/// temperature.
+const LEVEL_TEMPERATURE: f32 = 0.5;
 
 /// Returns true if the current particle was removed from the simulation during the tick
 pub fn tick_temperature(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
-    temperature_change_neighbors(x, y, sandbox);
+    conduct_heat(x, y, sandbox);
+    boil(sandbox.get_mut(x, y).unwrap());
+
+    try_ignite_burnable(x, y, sandbox);
+    try_extinquish_burning(x, y, sandbox);
+    heat_if_burning(x, y, sandbox);
 
     if tick_self(x, y, sandbox) {
         return true;
     }
 
-    try_ignite_burnable(x, y, sandbox);
-    try_extinquish_burning(x, y, sandbox);
     spark_if_ignited(x, y, sandbox);
 
     false
 }
 
-fn temperature_change_neighbors(x: usize, y: usize, sandbox: &mut Sandbox) {
-    let temperature_changer = match sandbox
+/// Exchanges heat with every neighbour that has a temperature, and with the air in empty cells.
+fn conduct_heat(x: usize, y: usize, sandbox: &mut Sandbox) {
+    let temperature = match sandbox
         .get(x, y)
         .expect("Simulation shouldn't have let it get this far")
-        .temperature_changer
+        .temperature
     {
-        Some(changer) => changer.0,
+        Some(temperature) => temperature,
         None => return,
     };
+    let ambient = sandbox.ambient();
 
+    let mut current = temperature.current_temperature;
     for (neighbor_x, neighbor_y) in [
         (x.overflowing_sub(1).0, y),
         (x + 1, y),
         (x, y.overflowing_sub(1).0),
         (x, y + 1),
     ] {
-        if let Some(particle) = sandbox.checked_get_mut(neighbor_x, neighbor_y) {
-            if let Some(temperature) = &mut particle.temperature {
-                if temperature_changer.is_positive() && !temperature.coolable {
-                    continue;
-                }
-                if temperature_changer.is_negative() && !temperature.heatable {
+        if sandbox.out_of_bounds_usize(neighbor_x, neighbor_y) {
+            continue;
+        }
+
+        match sandbox.checked_get_mut(neighbor_x, neighbor_y) {
+            Some(particle) => {
+                let Some(other) = &mut particle.temperature else {
                     continue;
+                };
+
+                let level = (current - other.current_temperature).abs() < LEVEL_TEMPERATURE;
+                let flow = heat_flow(
+                    (current, temperature.heat_capacity),
+                    (other.current_temperature, Some(other.heat_capacity)),
+                    conductance(temperature.conductivity, other.conductivity),
+                );
+                current -= flow / temperature.heat_capacity;
+                other.current_temperature += flow / other.heat_capacity;
+
+                // Both particles need to keep simulating until they're level
+                if !level {
+                    sandbox.weak_tick(neighbor_x, neighbor_y);
+                    sandbox.weak_tick(x, y);
                 }
+            }
+            None => {
+                let level = (current - ambient.temperature).abs() < LEVEL_TEMPERATURE;
+                let flow = heat_flow(
+                    (current, temperature.heat_capacity),
+                    (ambient.temperature, None),
+                    conductance(temperature.conductivity, ambient.conductivity),
+                );
+                current -= flow / temperature.heat_capacity;
 
-                match temperature.critical_on_cool {
-                    true => {
-                        temperature.current_temperature =
-                            (temperature.current_temperature + temperature_changer).clamp(1, 100)
-                    }
-                    false => {
-                        temperature.current_temperature = (temperature.current_temperature
-                            + temperature_changer)
-                            .clamp(0, temperature.starting_temperature)
-                    }
+                if !level {
+                    sandbox.weak_tick(x, y);
                 }
             }
         }
     }
+
+    if let Some(temperature) = &mut sandbox.get_mut(x, y).unwrap().temperature {
+        temperature.current_temperature = current;
+    }
+}
+
+/// Conductivity between two touching materials.
+fn conductance(a: f32, b: f32) -> f32 {
+    (a + b) / 2.0
+}
+
+/// Heat flowing from one particle to another during a tick, given their temperatures and heat
+/// capacities. A heat capacity of `None` never changes temperature, like the surrounding air.
+///
+/// The flow never goes past the point where both would have the same temperature, so heat
+/// can't oscillate between particles no matter how conductive they are.
+fn heat_flow(from: (f32, f32), to: (f32, Option<f32>), conductivity: f32) -> f32 {
+    let (from_temperature, from_capacity) = from;
+    let (to_temperature, to_capacity) = to;
+
+    let equilibrium = match to_capacity {
+        Some(to_capacity) => {
+            (from_temperature * from_capacity + to_temperature * to_capacity)
+                / (from_capacity + to_capacity)
+        }
+        None => to_temperature,
+    };
+    let limit = (from_temperature - equilibrium) * from_capacity;
+
+    // Halved since awake particles exchange heat from both sides of every pair
+    let flow = (from_temperature - to_temperature) * conductivity / 2.0;
+    if flow.abs() > limit.abs() {
+        limit
+    } else {
+        flow
+    }
 }
 
+/// Turns the particle critical while it's burning or past one of its critical temperatures.
 fn tick_self(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
-    let temperature = match sandbox
+    let particle = sandbox
         .get(x, y)
-        .expect("Simulation shouldn't have let it get this far")
-        .temperature
-    {
+        .expect("Simulation shouldn't have let it get this far");
+    let temperature = match particle.temperature {
         Some(temp) => temp,
         None => return false,
     };
+    let burning = particle.burnable.is_some_and(|burnable| burnable.burning);
 
-    let health = &mut sandbox.get_mut(x, y).unwrap().health;
-
-    if (!temperature.critical_on_cool && temperature.current_temperature <= 0)
-        || (temperature.critical_on_cool && temperature.current_temperature >= 100)
-    {
+    if burning || temperature.is_critical() {
         if temperature.explosion_radius > 0 {
             explode(x, y, temperature.explosion_radius, sandbox);
             return true;
         }
 
+        let health = &mut sandbox.get_mut(x, y).unwrap().health;
         deplete_critical(health);
 
         if health.amount <= 0 {
-            let replacement = temperature.change_on_critical.map(get_particle);
+            let mut replacement = temperature
+                .change_on_critical
+                .and_then(|material| sandbox.create_particle(material, x, y));
+
+            // Whatever it turns into keeps its heat, so cooling lava leaves hot rock behind
+            if let Some(replaced) = replacement
+                .as_mut()
+                .and_then(|particle| particle.temperature.as_mut())
+            {
+                replaced.current_temperature = temperature.current_temperature;
+            }
 
             sandbox.set(x, y, replacement);
             return true;
         }
 
-        sandbox.get_chunk_mut(x, y).weak_tick();
+        sandbox.weak_tick(x, y);
     }
 
     false
@@ -98,13 +175,15 @@ fn try_ignite_burnable(x: usize, y: usize, sandbox: &mut Sandbox) {
     let particle = sandbox
         .get_mut(x, y)
         .expect("Simulation shouldn't have let it get this far");
+    let Some(temperature) = particle.temperature else {
+        return;
+    };
     if let Some(burnable) = &mut particle.burnable {
-        if burnable.burning || particle.temperature.unwrap().current_temperature > 0 {
+        if burnable.burning || temperature.current_temperature < burnable.burn_temperature {
             return;
         }
 
         burnable.burning = true;
-        particle.temperature_changer = Some(TemperatureChanger(-1));
         particle.health.amount = burnable.burn_ticks;
         particle.color = burnable.burn_color;
     }
@@ -114,20 +193,35 @@ fn try_extinquish_burning(x: usize, y: usize, sandbox: &mut Sandbox) {
     let particle = sandbox
         .get_mut(x, y)
         .expect("Simulation shouldn't have let it get this far");
+    let Some(temperature) = particle.temperature else {
+        return;
+    };
     if let Some(burnable) = &mut particle.burnable {
-        if !burnable.burning || particle.temperature.unwrap().current_temperature <= 0 {
+        if !burnable.burning || temperature.current_temperature >= burnable.burn_temperature {
             return;
         }
 
         burnable.burning = false;
-        particle.temperature_changer = None;
         particle.health.amount = burnable.burn_ticks;
         particle.color = burnable.cooled_color;
-        particle.temperature.unwrap().current_temperature =
-            particle.temperature.unwrap().starting_temperature;
     }
 }
 
+fn heat_if_burning(x: usize, y: usize, sandbox: &mut Sandbox) {
+    let particle = sandbox
+        .get_mut(x, y)
+        .expect("Simulation shouldn't have let it get this far");
+    let (Some(burnable), Some(temperature)) = (particle.burnable, &mut particle.temperature) else {
+        return;
+    };
+    if !burnable.burning || temperature.current_temperature >= burnable.flame_temperature {
+        return;
+    }
+
+    temperature.current_temperature =
+        (temperature.current_temperature + BURN_HEAT).min(burnable.flame_temperature);
+}
+
 fn spark_if_ignited(x: usize, y: usize, sandbox: &mut Sandbox) {
     match sandbox
         .get_mut(x, y)
@@ -151,46 +245,40 @@ fn spark_if_ignited(x: usize, y: usize, sandbox: &mut Sandbox) {
         if sandbox.checked_get(neighbor_x, neighbor_y).is_none()
             && !sandbox.out_of_bounds_usize(neighbor_x, neighbor_y)
         {
-            let new_particle = if thread_rng().gen_ratio(1, 3) {
-                get_particle(ParticleTypes::Spark)
+            let material = if sandbox.rng(x, y).gen_ratio(1, 3) {
+                MaterialId::SPARK
             } else {
-                get_particle(ParticleTypes::Smoke)
+                MaterialId::SMOKE
             };
+            let new_particle = sandbox.create_particle(material, x, y);
 
-            sandbox.set(neighbor_x, neighbor_y, Some(new_particle));
+            sandbox.set(neighbor_x, neighbor_y, new_particle);
         }
     }
 }
 
-fn explode(current_x: usize, current_y: usize, radius: i32, sandbox: &mut Sandbox) {
-    let low_x = current_x as i32 - radius;
-    let high_x = current_x as i32 + radius;
-    let low_y = current_y as i32 - radius;
-    let high_y = current_y as i32 + radius;
+/// Turns everything within the radius into sparks and fills it with pressure, which throws the
+/// particles around it outwards.
+///
+/// Explosions reaching past the view of a tile go off once every tile was stepped.
+pub(crate) fn explode(current_x: usize, current_y: usize, radius: i32, sandbox: &mut Sandbox) {
+    if sandbox.defer_explosion(current_x, current_y, radius) {
+        return;
+    }
+
+    let pressure = radius as f32 * EXPLOSION_PRESSURE;
 
-    for x in (low_x - radius)..=(high_x + radius) {
-        for y in (low_y - radius)..=(high_y + radius) {
+    for x in (current_x as i32 - radius)..=(current_x as i32 + radius) {
+        for y in (current_y as i32 - radius)..=(current_y as i32 + radius) {
             if sandbox.out_of_bounds_i32(x, y) {
                 continue;
             }
 
-            if let Some(particle) = sandbox.get_mut(x as usize, y as usize) {
-                if x < low_x || x > high_x || y < low_y || y > high_y {
-                    let force = (Vec2::new(x as f32, y as f32)
-                        - Vec2::new(current_x as f32, current_y as f32))
-                    .normalize()
-                        * 10.0;
-                    particle.velocity = Velocity::new(force.x as i32, force.y.abs() as i32);
-
-                    continue;
-                }
-
-                sandbox.set(
-                    x as usize,
-                    y as usize,
-                    Some(get_particle(ParticleTypes::Spark)),
-                );
+            if sandbox.get(x as usize, y as usize).is_some() {
+                let spark = sandbox.create_particle(MaterialId::SPARK, current_x, current_y);
+                sandbox.set(x as usize, y as usize, spark);
             }
+            sandbox.add_pressure(x as usize, y as usize, pressure);
         }
     }
 }
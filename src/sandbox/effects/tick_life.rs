@@ -1,4 +1,4 @@
-use crate::sandbox::{particle_types::get_particle, sandbox::Sandbox};
+use crate::sandbox::sandbox::Sandbox;
 
 /// Returns true if the current particle was removed from the simulation during the tick
 pub fn tick_life(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
@@ -18,13 +18,13 @@ pub fn tick_life(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
     health.amount -= 1;
 
     if health.amount <= 0 {
-        let replacement = replacement.map(get_particle);
+        let replacement = replacement.and_then(|material| sandbox.create_particle(material, x, y));
 
         sandbox.set(x, y, replacement);
         return true;
     }
 
-    sandbox.get_chunk_mut(x, y).weak_tick();
+    sandbox.weak_tick(x, y);
 
     false
 }
@@ -0,0 +1,148 @@
+use rand::Rng;
+
+use crate::sandbox::{particle::*, sandbox::Sandbox};
+
+/// Wetness a particle gains from each water it soaks up. Particles too wet to soak up another
+/// count as soaked.
+const SOAK: f32 = 0.5;
+
+/// Share of the difference in wetness that wicks into a touching, drier absorbent particle every
+/// tick.
+const WICKING: f32 = 0.1;
+
+/// Temperature wet particles can't heat past until their water boils off, in degrees Celsius.
+const BOILING_POINT: f32 = 100.0;
+
+/// Heat it takes to boil a soaked particle dry.
+const LATENT_HEAT: f32 = 2000.0;
+
+/// Wetness below which a particle counts as dry, since wicking stops short of evening out.
+const DRY: f32 = 0.05;
+
+/// Difference in wetness below which touching particles count as evenly wet and stop wicking.
+/// Kept well below `DRY`, so the water left in a drying pile can't stay spread out in steps.
+const EVENLY_WET: f32 = 0.01;
+
+/// Returns true if the current particle was replaced during the tick
+pub fn tick_wetness(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let Some(absorbent) = sandbox
+        .get(x, y)
+        .expect("Simulation shouldn't have let it get this far")
+        .absorbent
+    else {
+        return false;
+    };
+
+    let mut wetness = absorbent.wetness;
+    let mut in_water = false;
+    let mut exposed = 0;
+    for (neighbor_x, neighbor_y) in [
+        (x.overflowing_sub(1).0, y),
+        (x + 1, y),
+        (x, y.overflowing_sub(1).0),
+        (x, y + 1),
+    ] {
+        if sandbox.out_of_bounds_usize(neighbor_x, neighbor_y) {
+            continue;
+        }
+
+        match sandbox.checked_get(neighbor_x, neighbor_y).copied() {
+            Some(other) if other.collision_type == CollisionType::Water => {
+                in_water = true;
+                if wetness > 1.0 - SOAK {
+                    continue;
+                }
+
+                // Keeps trying until it soaks the water up
+                if sandbox.rng(x, y).gen_bool(absorbent.soak_chance) {
+                    sandbox.set(neighbor_x, neighbor_y, None);
+                    wetness += SOAK;
+                }
+                sandbox.weak_tick(x, y);
+            }
+            Some(other) => {
+                let Some(other_absorbent) = other.absorbent else {
+                    continue;
+                };
+
+                // Water only wicks into drier particles, so the wetter one of a pair does the
+                // wicking and the drier one just wakes it up
+                let difference = wetness - other_absorbent.wetness;
+                if difference.abs() < EVENLY_WET {
+                    continue;
+                }
+                if difference < 0.0 {
+                    sandbox.weak_tick(neighbor_x, neighbor_y);
+                    continue;
+                }
+
+                let flow = difference * WICKING;
+                wetness -= flow;
+                sandbox
+                    .get_mut(neighbor_x, neighbor_y)
+                    .unwrap()
+                    .set_wetness(other_absorbent.wetness + flow);
+                sandbox.weak_tick(neighbor_x, neighbor_y);
+            }
+            None => exposed += 1,
+        }
+    }
+
+    // Only dries out in the air, so soaked particles stay wet under water
+    if !in_water {
+        wetness -= absorbent.dry_rate * exposed as f32;
+    }
+
+    let wetness = wetness.clamp(0.0, 1.0);
+    // Keeps simulating until it's done drying out and wicking
+    if wetness != absorbent.wetness {
+        sandbox.get_mut(x, y).unwrap().set_wetness(wetness);
+        sandbox.weak_tick(x, y);
+    }
+
+    change_state(x, y, sandbox)
+}
+
+/// Boils the water off a wet particle heated past boiling, which takes up the heat above it.
+pub(crate) fn boil(particle: &mut Particle) {
+    let (Some(absorbent), Some(temperature)) = (particle.absorbent, &mut particle.temperature)
+    else {
+        return;
+    };
+    if absorbent.wetness <= 0.0 || temperature.current_temperature <= BOILING_POINT {
+        return;
+    }
+
+    let excess_heat = (temperature.current_temperature - BOILING_POINT) * temperature.heat_capacity;
+    let boiled = absorbent.wetness.min(excess_heat / LATENT_HEAT);
+    temperature.current_temperature -= boiled * LATENT_HEAT / temperature.heat_capacity;
+    particle.set_wetness(absorbent.wetness - boiled);
+}
+
+/// Turns the particle into its wet or dry variant once it's soaked or dry, keeping its wetness
+/// and heat.
+fn change_state(x: usize, y: usize, sandbox: &mut Sandbox) -> bool {
+    let particle = *sandbox.get(x, y).unwrap();
+    let absorbent = particle.absorbent.unwrap();
+    let material = match absorbent.wetness {
+        wetness if wetness > 1.0 - SOAK => absorbent.soaked_as,
+        wetness if wetness < DRY => absorbent.dried_as,
+        _ => None,
+    };
+    let Some(material) = material else {
+        return false;
+    };
+
+    let Some(mut replacement) = sandbox.create_particle(material, x, y) else {
+        return false;
+    };
+    replacement.set_wetness(absorbent.wetness);
+    if let (Some(replaced), Some(temperature)) =
+        (&mut replacement.temperature, particle.temperature)
+    {
+        replaced.current_temperature = temperature.current_temperature;
+    }
+
+    sandbox.set(x, y, Some(replacement));
+    true
+}
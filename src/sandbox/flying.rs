@@ -0,0 +1,200 @@
+//! Particles flying freely above the grid, like debris thrown by an explosion or a splash.
+//!
+//! Particles thrown fast by a collision, a splash or pressure leave their cell and move
+//! ballistically with a float position until they slow down or hit something, then land back in
+//! the grid with the velocity they had left. Particles that merely fall stay in the grid. They
+//! don't react, burn or change temperature while flying.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    effects::movement::accelerate,
+    particle::{MovementType, Particle},
+    sandbox::Sandbox,
+};
+
+/// Speed in cells per tick at which powders and liquids leave the grid.
+const LAUNCH_SPEED: f32 = 4.0;
+
+/// Speed in cells per tick below which flying particles land again.
+const LAND_SPEED: f32 = 2.0;
+
+/// Cells a particle tries to land in, starting with its own.
+const NEIGHBORS: [(i32, i32); 9] = [
+    (0, 0),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (-1, 1),
+    (1, 1),
+    (-1, -1),
+    (1, -1),
+];
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct FlyingParticle {
+    /// The particle, which keeps moving with its own velocity.
+    pub particle: Particle,
+    /// Position in cells, where whole numbers are the centers of cells.
+    pub position: (f32, f32),
+}
+
+impl FlyingParticle {
+    /// Returns the cell the particle is flying through.
+    pub fn cell(&self) -> (i32, i32) {
+        cell(self.position)
+    }
+}
+
+/// Lets a fast powder or liquid that was thrown leave the grid when nothing is in its way,
+/// returning whether it was launched. Thrown particles that slowed down count as falling again.
+pub(crate) fn try_launch(x: usize, y: usize, heading: (i32, i32), sandbox: &mut Sandbox) -> bool {
+    let particle = sandbox.get_mut(x, y).unwrap();
+    if !particle.thrown {
+        return false;
+    }
+    if particle.velocity.speed() < LAUNCH_SPEED {
+        particle.thrown = false;
+        return false;
+    }
+
+    let particle = *particle;
+    if !matches!(
+        particle.movement_type,
+        MovementType::Powder | MovementType::Liquid
+    ) || heading == (0, 0)
+    {
+        return false;
+    }
+
+    let (next_x, next_y) = (x as i32 + heading.0, y as i32 + heading.1);
+    if sandbox.out_of_bounds_i32(next_x, next_y)
+        || sandbox.checked_get_i32(next_x, next_y).is_some()
+    {
+        return false;
+    }
+
+    sandbox.set(x, y, None);
+    sandbox.launch(FlyingParticle {
+        particle,
+        position: (x as f32, y as f32),
+    });
+
+    true
+}
+
+/// Moves every flying particle and lands the ones that slowed down or hit something.
+///
+/// Flying particles don't belong to a chunk, so they're moved once all tiles were stepped and
+/// can cross any number of chunks. They move in the order they were launched in, which doesn't
+/// depend on how the tiles were scheduled.
+pub(crate) fn update_flying(sandbox: &mut Sandbox) {
+    let flying = sandbox.take_flying();
+    let mut still_flying = Vec::with_capacity(flying.len());
+    for mut flying in flying {
+        accelerate(&mut flying.particle);
+        if fly(&mut flying, sandbox) {
+            still_flying.push(flying);
+        }
+    }
+
+    sandbox.set_flying(still_flying);
+}
+
+/// Lands every flying particle where it is, such as before its chunks are unloaded. Particles
+/// whose chunk is full land in the nearest empty cell of the sandbox instead.
+pub(crate) fn land_all(sandbox: &mut Sandbox) {
+    for flying in sandbox.take_flying() {
+        if land(flying, sandbox) {
+            continue;
+        }
+
+        let (width, height) = (sandbox.width(), sandbox.height());
+        if let Some((x, y)) = nearest_free(flying.cell(), 0..width, 0..height, sandbox) {
+            sandbox.set(x, y, Some(flying.particle));
+        }
+    }
+}
+
+/// Moves the particle by its velocity a cell at a time, returning whether it's still flying.
+///
+/// Particles with nowhere to land keep flying and try again during the next tick.
+fn fly(flying: &mut FlyingParticle, sandbox: &mut Sandbox) -> bool {
+    let velocity = flying.particle.velocity;
+    let speed = velocity.speed();
+    if speed < LAND_SPEED {
+        return !land(*flying, sandbox);
+    }
+
+    let steps = speed.ceil();
+    let step = (velocity.x / steps, velocity.y / steps);
+    for _ in 0..steps as u32 {
+        let next = (flying.position.0 + step.0, flying.position.1 + step.1);
+        let (next_x, next_y) = cell(next);
+        if cell(next) != flying.cell()
+            && (sandbox.out_of_bounds_i32(next_x, next_y)
+                || sandbox.checked_get_i32(next_x, next_y).is_some())
+        {
+            return !land(*flying, sandbox);
+        }
+
+        flying.position = next;
+    }
+
+    true
+}
+
+/// Puts the particle back into the grid at its cell or an empty one next to it. When something
+/// already landed all around it, it ends up in the nearest empty cell of its chunk instead.
+///
+/// Returns whether it landed, which it doesn't when its chunk is full.
+fn land(flying: FlyingParticle, sandbox: &mut Sandbox) -> bool {
+    let (x, y) = flying.cell();
+    let neighbor = NEIGHBORS
+        .into_iter()
+        .map(|(offset_x, offset_y)| (x + offset_x, y + offset_y))
+        .find(|&(x, y)| is_free(x, y, sandbox))
+        .map(|(x, y)| (x as usize, y as usize));
+
+    let free = neighbor.or_else(|| {
+        let (chunk_width, chunk_height) = (sandbox.chunk_width(), sandbox.chunk_height());
+        let chunk_x = x.clamp(0, sandbox.width() as i32 - 1) as usize / chunk_width * chunk_width;
+        let chunk_y =
+            y.clamp(0, sandbox.height() as i32 - 1) as usize / chunk_height * chunk_height;
+        nearest_free(
+            (x, y),
+            chunk_x..chunk_x + chunk_width,
+            chunk_y..chunk_y + chunk_height,
+            sandbox,
+        )
+    });
+    let Some((x, y)) = free else {
+        return false;
+    };
+
+    sandbox.set(x, y, Some(flying.particle));
+    true
+}
+
+/// Returns the empty cell of the area closest to the position.
+fn nearest_free(
+    (x, y): (i32, i32),
+    xs: Range<usize>,
+    ys: Range<usize>,
+    sandbox: &Sandbox,
+) -> Option<(usize, usize)> {
+    xs.flat_map(|cell_x| ys.clone().map(move |cell_y| (cell_x, cell_y)))
+        .filter(|&(cell_x, cell_y)| is_free(cell_x as i32, cell_y as i32, sandbox))
+        .min_by_key(|&(cell_x, cell_y)| (cell_x as i32 - x).pow(2) + (cell_y as i32 - y).pow(2))
+}
+
+fn is_free(x: i32, y: i32, sandbox: &Sandbox) -> bool {
+    !sandbox.out_of_bounds_i32(x, y) && sandbox.checked_get_i32(x, y).is_none()
+}
+
+fn cell((x, y): (f32, f32)) -> (i32, i32) {
+    (x.round() as i32, y.round() as i32)
+}
@@ -0,0 +1,183 @@
+//! Undo and redo for edits made to a [`Sandbox`] by the particle placer or custom tools.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use super::{particle::Particle, sandbox::Sandbox};
+
+/// Cells overwritten by a single operation, such as one stroke of the mouse.
+#[derive(Clone)]
+pub struct Edit {
+    /// Entity of the sandbox that was edited.
+    pub entity: Entity,
+    pub cells: Vec<CellEdit>,
+}
+
+#[derive(Clone, Copy)]
+pub struct CellEdit {
+    pub x: usize,
+    pub y: usize,
+    pub before: Option<Particle>,
+    pub after: Option<Particle>,
+}
+
+impl Edit {
+    /// Puts back every cell the edit overwrote.
+    pub fn revert(&self, sandbox: &mut Sandbox) {
+        for cell in self.cells.iter().rev() {
+            sandbox.set(cell.x, cell.y, cell.before);
+        }
+    }
+
+    /// Writes every cell of the edit again.
+    pub fn apply(&self, sandbox: &mut Sandbox) {
+        for cell in &self.cells {
+            sandbox.set(cell.x, cell.y, cell.after);
+        }
+    }
+}
+
+/// Edits that can be undone and redone, keeping at most `capacity` of them.
+///
+/// Tools either [`push`](EditHistory::push) whole edits, or [`record`](EditHistory::record)
+/// cells as they change and [`finish`](EditHistory::finish) the edit once they're done.
+#[derive(Resource)]
+pub struct EditHistory {
+    capacity: usize,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    edit: Edit,
+    /// Index of each recorded cell, so a cell changed twice keeps its first `before`.
+    indices: HashMap<(usize, usize), usize>,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl EditHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            undo: VecDeque::new(),
+            redo: vec![],
+            recording: None,
+        }
+    }
+
+    /// Adds an edit that was already applied, dropping the oldest one when the history is full.
+    pub fn push(&mut self, edit: Edit) {
+        self.finish();
+        self.push_finished(edit);
+    }
+
+    /// Adds a changed cell to the edit being recorded, starting a new one when needed.
+    ///
+    /// Changes to another sandbox finish the current edit first.
+    pub fn record(
+        &mut self,
+        entity: Entity,
+        x: usize,
+        y: usize,
+        before: Option<Particle>,
+        after: Option<Particle>,
+    ) {
+        if self
+            .recording
+            .as_ref()
+            .is_some_and(|recording| recording.edit.entity != entity)
+        {
+            self.finish();
+        }
+
+        let recording = self.recording.get_or_insert_with(|| Recording {
+            edit: Edit {
+                entity,
+                cells: vec![],
+            },
+            indices: HashMap::new(),
+        });
+
+        match recording.indices.get(&(x, y)) {
+            Some(&index) => recording.edit.cells[index].after = after,
+            None => {
+                recording.indices.insert((x, y), recording.edit.cells.len());
+                recording.edit.cells.push(CellEdit {
+                    x,
+                    y,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    /// Ends the edit being recorded so the next change starts a new one.
+    pub fn finish(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            self.push_finished(recording.edit);
+        }
+    }
+
+    /// Moves the latest edit to the redo stack and returns it so it can be reverted.
+    pub fn undo(&mut self) -> Option<&Edit> {
+        self.finish();
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit);
+        self.redo.last()
+    }
+
+    /// Moves the latest undone edit back to the undo stack and returns it so it can be applied.
+    pub fn redo(&mut self) -> Option<&Edit> {
+        self.finish();
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit);
+        self.undo.back()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.recording.is_some()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Drops every edit of the sandbox, such as after it was replaced.
+    pub fn forget(&mut self, entity: Entity) {
+        if self
+            .recording
+            .as_ref()
+            .is_some_and(|recording| recording.edit.entity == entity)
+        {
+            self.recording = None;
+        }
+        self.undo.retain(|edit| edit.entity != entity);
+        self.redo.retain(|edit| edit.entity != entity);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.recording = None;
+    }
+
+    fn push_finished(&mut self, edit: Edit) {
+        if edit.cells.is_empty() || self.capacity == 0 {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+}
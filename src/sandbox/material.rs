@@ -0,0 +1,417 @@
+//! Materials are the kinds of particles a [`Sandbox`] can create, identified by a [`MaterialId`].
+//!
+//! The built-in materials are always registered first so their ids never change. Games can add
+//! their own with [`RegisterMaterialExt::register_material`] or [`MaterialRegistry::register`].
+
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    builtin_materials::builtin_materials, particle::*, particle_definition::ParticleDefinition,
+    sandbox::Sandbox, simulation::update_particles,
+};
+
+pub struct MaterialPlugin;
+
+impl Plugin for MaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MaterialRegistry>().add_systems(
+            Update,
+            (check_materials, sync_materials.before(update_particles)),
+        );
+    }
+}
+
+/// Identifies a material inside a [`MaterialRegistry`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MaterialId(pub u16);
+
+impl MaterialId {
+    pub const SAND: Self = Self(0);
+    pub const WATER: Self = Self(1);
+    pub const STONE: Self = Self(2);
+    pub const STEAM: Self = Self(3);
+    pub const ACID: Self = Self(4);
+    pub const WOOD: Self = Self(5);
+    pub const GLASS: Self = Self(6);
+    pub const SPARK: Self = Self(7);
+    pub const EMBER: Self = Self(8);
+    pub const SMOKE: Self = Self(9);
+    pub const LAVA: Self = Self(10);
+    pub const OIL: Self = Self(11);
+    pub const GUNPOWDER: Self = Self(12);
+    pub const TNT: Self = Self(13);
+    pub const ASH: Self = Self(14);
+    pub const DIRT: Self = Self(15);
+    pub const GRASS: Self = Self(16);
+    pub const ALCOHOL: Self = Self(17);
+    pub const IGNEOUS: Self = Self(18);
+    pub const INDESTRUCTIBLE: Self = Self(19);
+    pub const MUD: Self = Self(20);
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Every known material, shared with each [`Sandbox`] so the simulation can create particles.
+///
+/// Cloning is cheap, and the sandboxes pick up changes at the start of the next update.
+#[derive(Resource, Clone)]
+pub struct MaterialRegistry {
+    materials: Arc<Materials>,
+}
+
+#[derive(Clone, Default)]
+struct Materials {
+    ids: HashMap<String, MaterialId>,
+    entries: Vec<Material>,
+    /// Reactions by the material of the particle reacting and the one it touches.
+    reactions: HashMap<(MaterialId, MaterialId), Reaction>,
+}
+
+#[derive(Clone)]
+struct Material {
+    name: String,
+    /// `None` while the material has only been referenced by another one.
+    prototype: Option<Prototype>,
+    /// Whether any reaction starts from this material, so most particles skip the lookups.
+    reactive: bool,
+}
+
+/// What happens when a particle touches a particle of another material.
+#[derive(Clone, Copy, Debug)]
+pub struct Reaction {
+    /// Chance to react with each touching particle every tick.
+    pub chance: f64,
+    pub becomes: Product<MaterialId>,
+    pub other_becomes: Product<MaterialId>,
+    /// Material created in an empty cell next to the particle.
+    pub byproduct: Option<MaterialId>,
+}
+
+/// What a particle turns into after a reaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Product<T> {
+    #[default]
+    Unchanged,
+    /// The particle is removed.
+    Empty,
+    Material(T),
+}
+
+/// A particle ready to be copied, with the parts that are rolled for every new particle.
+#[derive(Clone)]
+struct Prototype {
+    particle: Particle,
+    lifetime: Option<(i32, i32)>,
+}
+
+impl Default for MaterialRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            materials: Arc::default(),
+        };
+
+        // Reserved up front since the built-in materials reference each other
+        let builtins = builtin_materials();
+        let materials = Arc::make_mut(&mut registry.materials);
+        for (id, definition) in &builtins {
+            let reserved = materials.reserve(&definition.name);
+            assert_eq!(reserved, *id, "Built-in materials must keep their ids");
+        }
+
+        for (_, definition) in builtins {
+            registry.register(definition);
+        }
+
+        registry
+    }
+}
+
+impl MaterialRegistry {
+    /// Adds a material, or replaces the prototype and reactions of the one with the same name.
+    ///
+    /// Materials referenced by the definition get an id right away, so they can be
+    /// registered afterwards.
+    pub fn register(&mut self, definition: ParticleDefinition) -> MaterialId {
+        let materials = Arc::make_mut(&mut self.materials);
+        let id = materials.reserve(&definition.name);
+        let prototype = Prototype::new(id, &definition, materials);
+        materials.entries[id.index()].prototype = Some(prototype);
+
+        materials
+            .reactions
+            .retain(|(reactant, _), _| *reactant != id);
+        for reaction in &definition.reactions {
+            let other = materials.reserve(&reaction.with);
+            let mut product = |product: &Product<String>| match product {
+                Product::Unchanged => Product::Unchanged,
+                Product::Empty => Product::Empty,
+                Product::Material(name) => Product::Material(materials.reserve(name)),
+            };
+            let becomes = product(&reaction.becomes);
+            let other_becomes = product(&reaction.other_becomes);
+            let byproduct = reaction
+                .byproduct
+                .as_deref()
+                .map(|name| materials.reserve(name));
+
+            materials.reactions.insert(
+                (id, other),
+                Reaction {
+                    chance: reaction.chance,
+                    becomes,
+                    other_becomes,
+                    byproduct,
+                },
+            );
+        }
+        materials.entries[id.index()].reactive = !definition.reactions.is_empty();
+
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<MaterialId> {
+        self.materials.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: MaterialId) -> Option<&str> {
+        self.materials
+            .entries
+            .get(id.index())
+            .map(|material| material.name.as_str())
+    }
+
+    /// Iterates the names of every material by id, including the ones without a prototype.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.materials
+            .entries
+            .iter()
+            .map(|material| material.name.as_str())
+    }
+
+    /// Iterates the ids and names of every material with a prototype.
+    pub fn iter(&self) -> impl Iterator<Item = (MaterialId, &str)> {
+        self.materials
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, material)| material.prototype.is_some())
+            .map(|(i, material)| (MaterialId(i as u16), material.name.as_str()))
+    }
+
+    /// Returns the particle new particles of the material are copied from, before anything is rolled.
+    pub fn prototype(&self, id: MaterialId) -> Option<&Particle> {
+        let prototype = self.materials.entries.get(id.index())?.prototype.as_ref()?;
+        Some(&prototype.particle)
+    }
+
+    /// Returns the reaction of a particle of `material` touching one of `other`, or `None` until
+    /// every material it produces is registered.
+    pub fn reaction(&self, material: MaterialId, other: MaterialId) -> Option<&Reaction> {
+        let reaction = self.materials.reactions.get(&(material, other))?;
+        self.missing_product(reaction).is_none().then_some(reaction)
+    }
+
+    /// Checks that every reaction only produces registered materials, since the others were
+    /// only referenced by name and have nothing to create particles from.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut reactions = self.materials.reactions.iter().collect::<Vec<_>>();
+        reactions.sort_unstable_by_key(|(&materials, _)| materials);
+
+        for (&(material, other), reaction) in reactions {
+            if let Some(product) = self.missing_product(reaction) {
+                let name = |id| self.name(id).unwrap_or("?");
+                return Err(format!(
+                    "reaction of {} with {} produces {}, which isn't registered",
+                    name(material),
+                    name(other),
+                    name(product)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a material the reaction produces that isn't registered.
+    fn missing_product(&self, reaction: &Reaction) -> Option<MaterialId> {
+        [reaction.becomes, reaction.other_becomes]
+            .into_iter()
+            .filter_map(|product| match product {
+                Product::Material(material) => Some(material),
+                Product::Unchanged | Product::Empty => None,
+            })
+            .chain(reaction.byproduct)
+            .find(|&material| self.prototype(material).is_none())
+    }
+
+    /// Whether particles of the material react with any other material.
+    pub fn is_reactive(&self, material: MaterialId) -> bool {
+        self.materials
+            .entries
+            .get(material.index())
+            .is_some_and(|material| material.reactive)
+    }
+
+    /// Creates a particle of the material, or `None` when it was never registered.
+    pub fn create(&self, id: MaterialId, rng: &mut impl Rng) -> Option<Particle> {
+        let prototype = self.materials.entries.get(id.index())?.prototype.as_ref()?;
+        Some(prototype.create(rng))
+    }
+}
+
+impl Materials {
+    fn reserve(&mut self, name: &str) -> MaterialId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = MaterialId(
+            self.entries
+                .len()
+                .try_into()
+                .expect("Too many materials were registered"),
+        );
+        self.ids.insert(name.to_string(), id);
+        self.entries.push(Material {
+            name: name.to_string(),
+            prototype: None,
+            reactive: false,
+        });
+
+        id
+    }
+}
+
+impl Prototype {
+    fn new(id: MaterialId, definition: &ParticleDefinition, materials: &mut Materials) -> Self {
+        let mut reference =
+            |name: &Option<String>| name.as_deref().map(|name| materials.reserve(name));
+
+        let mut particle = Particle {
+            material: id,
+            health: definition.health,
+            velocity: Velocity::default(),
+            color: definition.color,
+            movement_type: definition.movement_type,
+            density: Density(definition.density),
+            dispersion: definition.dispersion,
+            viscosity: definition.viscosity,
+            burst_pressure: definition.burst_pressure,
+            acidity: definition.acidity.map(Acidity),
+            temperature: definition.temperature.as_ref().map(|temperature| {
+                Temperature::new(
+                    temperature.starting_temperature,
+                    temperature.conductivity,
+                    temperature.heat_capacity,
+                    temperature.critical_above,
+                    temperature.critical_below,
+                    reference(&temperature.change_on_critical),
+                    temperature.explosion_radius,
+                )
+            }),
+            burnable: definition.burnable.map(|burnable| Burnable {
+                burn_temperature: burnable.burn_temperature,
+                flame_temperature: burnable.flame_temperature,
+                burn_ticks: burnable.burn_ticks,
+                burn_color: burnable.burn_color,
+                cooled_color: burnable.cooled_color,
+                burning: false,
+            }),
+            absorbent: definition.absorbent.as_ref().map(|absorbent| Absorbent {
+                wetness: 0.0,
+                soak_chance: absorbent.soak_chance,
+                dry_rate: absorbent.dry_rate,
+                dry_color: definition.color,
+                wet_color: absorbent.wet_color,
+                wet_friction: absorbent.wet_friction.unwrap_or(definition.friction),
+                soaked_as: reference(&absorbent.soaked_as),
+                dried_as: reference(&absorbent.dried_as),
+            }),
+            tick_life: definition.tick_life.as_ref().map(|tick_life| TickLife {
+                replace_on_death: reference(&tick_life.replace_on_death),
+            }),
+            growable: definition.growable.as_ref().map(|growable| Growable {
+                energy: growable.energy,
+                spread_chance: growable.spread_chance,
+                grow_as: materials.reserve(&growable.grow_as),
+                up_chance: growable.up_chance,
+                can_sprout: false,
+            }),
+            collision_type: definition.collision_type,
+            affected_by_gravity: definition.affected_by_gravity,
+            gravity_scale: definition.gravity_scale,
+            terminal_velocity: definition.terminal_velocity,
+            air_drag: definition.air_drag,
+            restitution: definition.restitution,
+            friction: definition.friction,
+            resting: false,
+            thrown: false,
+            growable_on: definition.growable_on,
+            updated: false,
+        };
+        if let Some(absorbent) = &definition.absorbent {
+            particle.set_wetness(absorbent.wetness);
+        }
+
+        Self {
+            particle,
+            lifetime: definition
+                .tick_life
+                .as_ref()
+                .and_then(|tick_life| tick_life.lifetime),
+        }
+    }
+
+    fn create(&self, rng: &mut impl Rng) -> Particle {
+        let mut particle = self.particle;
+        if let Some((min, max)) = self.lifetime {
+            particle.health.amount = rng.gen_range(min..max);
+        }
+        if let Some(growable) = &mut particle.growable {
+            growable.can_sprout = rng.gen_bool(growable.up_chance);
+        }
+
+        particle
+    }
+}
+
+/// Registers materials from a game's own plugins.
+pub trait RegisterMaterialExt {
+    fn register_material(&mut self, definition: ParticleDefinition) -> &mut Self;
+}
+
+impl RegisterMaterialExt for App {
+    fn register_material(&mut self, definition: ParticleDefinition) -> &mut Self {
+        self.init_resource::<MaterialRegistry>();
+        self.world_mut()
+            .resource_mut::<MaterialRegistry>()
+            .register(definition);
+        self
+    }
+}
+
+/// Warns about reactions that won't happen since they produce a material that isn't
+/// registered, which may only be until the definition of the material finished loading.
+fn check_materials(registry: Res<MaterialRegistry>) {
+    if !registry.is_changed() {
+        return;
+    }
+
+    if let Err(reason) = registry.validate() {
+        warn!("Invalid materials: {}", reason);
+    }
+}
+
+fn sync_materials(registry: Res<MaterialRegistry>, mut sandbox_query: Query<&mut Sandbox>) {
+    for mut sandbox in &mut sandbox_query {
+        if registry.is_changed() || sandbox.is_added() {
+            sandbox.set_materials(registry.clone());
+        }
+    }
+}
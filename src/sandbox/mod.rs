@@ -7,61 +7,183 @@ use std::f32::consts::PI;
 use std::time::Duration;
 
 use self::{
-    collider::SandboxColliderPlugin, particle_placer::ParticlePlacerPlugin,
-    render::render_particles, sandbox::Sandbox, simulation::update_particles,
+    collider::{ColliderStorage, SandboxColliderPlugin},
+    history::EditHistory,
+    material::MaterialPlugin,
+    particle::Ambient,
+    particle_definition::ParticleDefinitionPlugin,
+    particle_placer::ParticlePlacerPlugin,
+    render::render_particles,
+    sandbox::Sandbox,
+    simulation::{update_particles, ChunkUpdateMode, SweepStrategy},
+    streaming::SandboxStreamingPlugin,
 };
 
-mod chunk;
+mod builtin_materials;
+pub mod chunk;
 pub mod collider;
 mod effects;
+pub mod flying;
+pub mod history;
+pub mod material;
+pub mod palette;
 pub mod particle;
+pub mod particle_definition;
 mod particle_placer;
-pub mod particle_types;
+mod pressure;
 mod render;
+pub mod replay;
+pub mod rng;
 pub mod sandbox;
-mod simulation;
+pub mod save;
+pub mod simulation;
+pub mod streaming;
 
-const SANDBOX_CHUNK_WIDTH: usize = 8;
-const SANDBOX_CHUNK_HEIGHT: usize = 8;
-const SANDBOX_X_CHUNKS: usize = 30;
-const SANDBOX_Y_CHUNKS: usize = 17;
+#[derive(Default)]
+pub struct SandboxPlugin {
+    pub config: SandboxConfig,
+}
 
-pub struct SandboxPlugin;
+impl SandboxPlugin {
+    pub fn new(config: SandboxConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Plugin for SandboxPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ParticlePlacerPlugin)
+        let tick = on_timer(Duration::from_secs_f32(1.0 / self.config.tick_rate));
+
+        app.insert_resource(self.config.clone())
+            .init_resource::<EditHistory>()
             .add_plugins(SandboxColliderPlugin)
-            .add_systems(Startup, setup)
-            .add_systems(
+            .add_plugins(SandboxStreamingPlugin)
+            .add_plugins(MaterialPlugin)
+            .add_plugins(ParticleDefinitionPlugin {
+                paths: self.config.particle_definitions.clone(),
+            });
+
+        if self.config.headless {
+            app.add_systems(Update, update_particles.run_if(tick));
+        } else {
+            app.add_plugins(ParticlePlacerPlugin).add_systems(
                 Update,
                 (update_particles, render_particles)
                     .chain()
-                    .distributive_run_if(on_timer(Duration::from_secs_f32(1.0 / 24.0))),
+                    .distributive_run_if(tick),
             );
+        }
+
+        if self.config.spawn_default_sandbox {
+            match self.config.headless {
+                true => app.add_systems(Startup, setup_headless),
+                false => app.add_systems(Startup, setup),
+            };
+        }
+    }
+}
+
+/// Settings used by the [`SandboxPlugin`] and its systems.
+#[derive(Resource, Clone, Debug)]
+pub struct SandboxConfig {
+    /// Number of chunks along the x axis.
+    pub x_chunks: usize,
+    /// Number of chunks along the y axis.
+    pub y_chunks: usize,
+    /// Width of a single chunk in particles.
+    pub chunk_width: usize,
+    /// Height of a single chunk in particles.
+    pub chunk_height: usize,
+    /// Simulation steps per second.
+    pub tick_rate: f32,
+    /// Size of a single particle in world units.
+    pub pixel_scale: f32,
+    /// Seed for the simulation's random number generators.
+    pub seed: u64,
+    /// Whether chunks are updated on the current thread or in parallel.
+    pub update_mode: ChunkUpdateMode,
+    /// Order the particles are updated in during a tick.
+    pub sweep: SweepStrategy,
+    /// Air temperature of spawned sandboxes.
+    pub ambient: Ambient,
+    /// Asset paths of `.particle.ron` and `.particle.json` files that register materials,
+    /// replacing the ones with the same name. Requires Bevy's `AssetPlugin` when not empty.
+    pub particle_definitions: Vec<String>,
+    /// Spawns a sandbox on startup when enabled.
+    pub spawn_default_sandbox: bool,
+    /// Runs the simulation without rendering or the particle placer,
+    /// so no window, camera or image assets are required.
+    pub headless: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            x_chunks: 30,
+            y_chunks: 17,
+            chunk_width: 8,
+            chunk_height: 8,
+            tick_rate: 24.0,
+            pixel_scale: 8.0,
+            seed: 0,
+            update_mode: ChunkUpdateMode::Serial,
+            sweep: SweepStrategy::ColumnMajor,
+            ambient: Ambient::default(),
+            particle_definitions: vec![],
+            spawn_default_sandbox: true,
+            headless: false,
+        }
     }
 }
 
-fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
-    spawn_sandbox(
-        &mut commands,
-        &mut images,
-        SANDBOX_X_CHUNKS,
-        SANDBOX_Y_CHUNKS,
+impl SandboxConfig {
+    pub fn width(&self) -> usize {
+        self.x_chunks * self.chunk_width
+    }
+
+    pub fn height(&self) -> usize {
+        self.y_chunks * self.chunk_height
+    }
+}
+
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>, config: Res<SandboxConfig>) {
+    spawn_sandbox(&mut commands, &mut images, &config, Vec2::ZERO);
+}
+
+fn setup_headless(mut commands: Commands, config: Res<SandboxConfig>) {
+    spawn_headless_sandbox(&mut commands, &config);
+}
+
+/// Spawns a [`Sandbox`] without a sprite or image to render into.
+pub fn spawn_headless_sandbox(commands: &mut Commands, config: &SandboxConfig) -> Entity {
+    let mut sandbox = Sandbox::new(
+        config.x_chunks,
+        config.y_chunks,
+        config.chunk_width,
+        config.chunk_height,
+        config.seed,
     );
+    sandbox.set_ambient(config.ambient);
+
+    commands
+        .spawn((
+            sandbox,
+            ColliderStorage::new(config.x_chunks * config.y_chunks),
+        ))
+        .id()
 }
 
 pub fn spawn_sandbox(
     commands: &mut Commands,
     images: &mut Assets<Image>,
-    x_chunks: usize,
-    y_chunks: usize,
-) {
+    config: &SandboxConfig,
+    position: Vec2,
+) -> Entity {
     let image_handle = {
         let mut image = Image::new_fill(
             Extent3d {
-                width: (x_chunks * SANDBOX_CHUNK_WIDTH) as u32,
-                height: (y_chunks * SANDBOX_CHUNK_HEIGHT) as u32,
+                width: config.width() as u32,
+                height: config.height() as u32,
                 depth_or_array_layers: 1,
             },
             TextureDimension::D2,
@@ -73,20 +195,16 @@ pub fn spawn_sandbox(
         images.add(image)
     };
 
-    commands
-        .spawn(Sandbox::new(
-            x_chunks,
-            y_chunks,
-            SANDBOX_CHUNK_WIDTH,
-            SANDBOX_CHUNK_HEIGHT,
-        ))
-        .insert(SpriteBundle {
-            texture: image_handle,
-            transform: Transform {
-                translation: Vec3::new(0.0, 0.0, 1.0),
-                scale: Vec3::new(8.0, 8.0, 1.0),
-                rotation: Quat::from_euler(EulerRot::XYZ, 0.0, PI, PI),
-            },
-            ..Default::default()
-        });
+    let entity = spawn_headless_sandbox(commands, config);
+    commands.entity(entity).insert(SpriteBundle {
+        texture: image_handle,
+        transform: Transform {
+            translation: position.extend(1.0),
+            scale: Vec3::new(config.pixel_scale, config.pixel_scale, 1.0),
+            rotation: Quat::from_euler(EulerRot::XYZ, 0.0, PI, PI),
+        },
+        ..Default::default()
+    });
+
+    entity
 }
@@ -0,0 +1,189 @@
+//! Imports and exports the cells of a [`Sandbox`] as PNG images.
+//!
+//! Each pixel is a cell, with the top row of the image at the top of the sandbox. A pixel with
+//! an alpha of 0 is an empty cell, and every other pixel is turned into a material through a
+//! [`Palette`].
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+
+use super::{
+    material::{MaterialId, MaterialRegistry},
+    sandbox::Sandbox,
+    save::invalid_data,
+};
+
+/// Maps the colours of an image to materials.
+#[derive(Clone, Default)]
+pub struct Palette {
+    colors: Vec<((u8, u8, u8), MaterialId)>,
+}
+
+impl Palette {
+    /// Maps the colour of every registered material to it. When two materials share a colour,
+    /// the one with the lower id is used.
+    pub fn new(materials: &MaterialRegistry) -> Self {
+        let mut palette = Self::default();
+        for (id, _) in materials.iter() {
+            let Some(prototype) = materials.prototype(id) else {
+                continue;
+            };
+
+            let (r, g, b, _) = prototype.color;
+            if palette.get((r, g, b)).is_none() {
+                palette.insert((r, g, b), id);
+            }
+        }
+
+        palette
+    }
+
+    /// Maps the colours written by [`PngExport::MaterialIndices`] back to their materials.
+    pub fn material_indices(materials: &MaterialRegistry) -> Self {
+        let mut palette = Self::default();
+        for (id, _) in materials.iter() {
+            palette.insert(index_color(id), id);
+        }
+
+        palette
+    }
+
+    /// Maps the colour to the material, replacing the material it was mapped to before.
+    pub fn insert(&mut self, color: (u8, u8, u8), material: MaterialId) {
+        match self.colors.iter_mut().find(|(other, _)| *other == color) {
+            Some(entry) => entry.1 = material,
+            None => self.colors.push((color, material)),
+        }
+    }
+
+    /// Returns the material mapped to exactly this colour.
+    pub fn get(&self, color: (u8, u8, u8)) -> Option<MaterialId> {
+        self.colors
+            .iter()
+            .find(|(other, _)| *other == color)
+            .map(|(_, material)| *material)
+    }
+
+    /// Returns the material with the closest colour, so slightly off colours from
+    /// antialiasing or compression still map to something sensible.
+    pub fn nearest(&self, color: (u8, u8, u8)) -> Option<MaterialId> {
+        let distance = |other: (u8, u8, u8)| {
+            let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            channel(color.0, other.0) + channel(color.1, other.1) + channel(color.2, other.2)
+        };
+
+        self.colors
+            .iter()
+            .min_by_key(|(other, _)| distance(*other))
+            .map(|(_, material)| *material)
+    }
+}
+
+/// What the pixels of an exported image hold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngExport {
+    /// The current colour of every particle, as it's rendered.
+    #[default]
+    Colors,
+    /// The material id of every particle, with the low byte in red and the high byte in green.
+    MaterialIndices,
+}
+
+fn index_color(id: MaterialId) -> (u8, u8, u8) {
+    let [low, high] = id.0.to_le_bytes();
+    (low, high, 0)
+}
+
+impl Sandbox {
+    /// Replaces every cell with the pixels of a PNG the same size as the sandbox.
+    ///
+    /// Colours missing from the palette use the material with the closest colour.
+    pub fn import_png(&mut self, reader: impl Read, palette: &Palette) -> io::Result<()> {
+        let mut decoder = Decoder::new(reader);
+        decoder.set_transformations(Transformations::normalize_to_color8());
+        let mut png = decoder.read_info().map_err(invalid_data)?;
+        let mut buffer = vec![0; png.output_buffer_size()];
+        let info = png.next_frame(&mut buffer).map_err(invalid_data)?;
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        if (width, height) != (self.width(), self.height()) {
+            return Err(invalid_data(format!(
+                "image is {}x{} but the sandbox is {}x{}",
+                width,
+                height,
+                self.width(),
+                self.height()
+            )));
+        }
+
+        let channels = match info.color_type {
+            ColorType::Grayscale => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+            ColorType::Indexed => unreachable!("Indexed images are expanded when decoding"),
+        };
+
+        let mut materials = HashMap::new();
+        for (i, pixel) in buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .enumerate()
+        {
+            let (color, alpha) = match *pixel {
+                [gray] => ((gray, gray, gray), 255),
+                [gray, alpha] => ((gray, gray, gray), alpha),
+                [r, g, b] => ((r, g, b), 255),
+                [r, g, b, alpha] => ((r, g, b), alpha),
+                _ => unreachable!(),
+            };
+
+            let (x, y) = (i % width, height - 1 - i / width);
+            let particle = match alpha {
+                0 => None,
+                _ => {
+                    let material = *materials
+                        .entry(color)
+                        .or_insert_with(|| palette.get(color).or_else(|| palette.nearest(color)));
+                    material.and_then(|material| self.create_particle(material, x, y))
+                }
+            };
+            self.set(x, y, particle);
+        }
+
+        Ok(())
+    }
+
+    /// Writes every cell to an RGBA PNG the same size as the sandbox.
+    pub fn export_png(&self, writer: impl Write, export: PngExport) -> io::Result<()> {
+        let (width, height) = (self.width(), self.height());
+
+        let mut data = Vec::with_capacity(width * height * 4);
+        for row in 0..height {
+            for x in 0..width {
+                let rgba = match (self.get(x, height - 1 - row), export) {
+                    (None, _) => [0; 4],
+                    (Some(particle), PngExport::Colors) => {
+                        let (r, g, b, a) = particle.color;
+                        [r, g, b, a]
+                    }
+                    (Some(particle), PngExport::MaterialIndices) => {
+                        let (r, g, b) = index_color(particle.material);
+                        [r, g, b, 255]
+                    }
+                };
+                data.extend_from_slice(&rgba);
+            }
+        }
+
+        let mut encoder = Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        let mut png = encoder.write_header().map_err(invalid_data)?;
+        png.write_image_data(&data).map_err(invalid_data)?;
+        png.finish().map_err(invalid_data)
+    }
+}
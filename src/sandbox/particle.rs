@@ -1,30 +1,95 @@
-use std::cmp::Ordering;
 use std::slice::Iter;
 
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use super::particle_types::ParticleTypes;
+use super::material::MaterialId;
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Particle {
+    /// The material the particle was created from, which stays the same when its properties change.
+    pub material: MaterialId,
     pub health: ParticleHealth,
     pub velocity: Velocity,
     pub color: (u8, u8, u8, u8),
     pub movement_type: MovementType,
     pub density: Density,
+    /// Cells a liquid or gas flows sideways in a single tick when it can't move any further.
+    pub dispersion: u32,
+    /// Liquids and gases only flow sideways once every this many ticks on average.
+    pub viscosity: u32,
+    /// Gas pressure next to the particle that breaks it, leaving its cell empty.
+    pub burst_pressure: Option<f32>,
     pub acidity: Option<Acidity>,
     pub temperature: Option<Temperature>,
-    pub temperature_changer: Option<TemperatureChanger>,
     pub burnable: Option<Burnable>,
+    pub absorbent: Option<Absorbent>,
     pub tick_life: Option<TickLife>,
     pub growable: Option<Growable>,
     pub collision_type: CollisionType,
     pub affected_by_gravity: bool,
+    /// Multiplies the gravity the particle falls or rises with.
+    pub gravity_scale: f32,
+    /// Fastest the particle moves in cells per tick.
+    pub terminal_velocity: f32,
+    /// Share of its velocity the particle loses to the air every tick.
+    pub air_drag: f32,
+    /// Share of its speed the particle keeps when bouncing off what it runs into.
+    pub restitution: f32,
+    /// Chance a powder stops sliding once it lands, so higher values pile up steeper.
+    pub friction: f32,
+    /// Whether the powder settled, so it only falls straight down until something knocks it loose.
+    pub resting: bool,
+    /// Whether a collision, splash or pressure threw the particle, which lets it fly off the grid
+    /// while it's fast. Falling alone never does.
+    #[serde(skip)]
+    pub thrown: bool,
+    #[serde(skip)]
     pub updated: bool,
     pub growable_on: bool,
 }
 
-#[derive(Clone, Copy)]
+impl Particle {
+    /// Replaces its own material and every material it can turn into.
+    pub fn map_materials(&mut self, mut map: impl FnMut(MaterialId) -> MaterialId) {
+        self.material = map(self.material);
+        if let Some(temperature) = &mut self.temperature {
+            temperature.change_on_critical = temperature.change_on_critical.map(&mut map);
+        }
+        if let Some(tick_life) = &mut self.tick_life {
+            tick_life.replace_on_death = tick_life.replace_on_death.map(&mut map);
+        }
+        if let Some(growable) = &mut self.growable {
+            growable.grow_as = map(growable.grow_as);
+        }
+        if let Some(absorbent) = &mut self.absorbent {
+            absorbent.soaked_as = absorbent.soaked_as.map(&mut map);
+            absorbent.dried_as = absorbent.dried_as.map(&mut map);
+        }
+    }
+
+    /// Friction of the powder, which grows towards its wet friction as it soaks up water.
+    pub fn current_friction(&self) -> f32 {
+        match self.absorbent {
+            Some(absorbent) => {
+                self.friction + (absorbent.wet_friction - self.friction) * absorbent.wetness
+            }
+            None => self.friction,
+        }
+    }
+
+    /// Sets how wet the particle is and shades it to match. Does nothing if it can't get wet.
+    pub fn set_wetness(&mut self, wetness: f32) {
+        let Some(absorbent) = &mut self.absorbent else {
+            return;
+        };
+
+        absorbent.wetness = wetness.clamp(0.0, 1.0);
+        self.color = absorbent.color();
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct ParticleHealth {
     pub amount: i32,
     pub corrodable: bool,
@@ -45,103 +110,198 @@ impl Default for ParticleHealth {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TickLife {
-    pub replace_on_death: Option<ParticleTypes>,
+    pub replace_on_death: Option<MaterialId>,
 }
 
-#[derive(Default, Clone, Copy, Debug, PartialEq)]
+/// Cells moved per tick, along with the part of a cell moved so far that doesn't add up to a
+/// whole one yet.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Velocity {
-    pub x: i32,
-    pub y: i32,
+    pub x: f32,
+    pub y: f32,
+    remainder_x: f32,
+    remainder_y: f32,
 }
 
 impl Velocity {
-    pub fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            ..Self::default()
+        }
     }
 
-    pub fn zero_out(&mut self) {
-        match self.x.cmp(&0) {
-            Ordering::Less => self.x += 1,
-            Ordering::Greater => self.x -= 1,
-            _ => (),
-        }
+    /// Moves the particle by its velocity and returns the whole cells it crossed this tick,
+    /// keeping the rest for the following ticks.
+    pub fn step(&mut self) -> (i32, i32) {
+        self.remainder_x += self.x;
+        self.remainder_y += self.y;
+        let (cells_x, cells_y) = (self.remainder_x.trunc(), self.remainder_y.trunc());
+        self.remainder_x -= cells_x;
+        self.remainder_y -= cells_y;
+
+        (cells_x as i32, cells_y as i32)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.x.hypot(self.y)
     }
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Density(pub u32);
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Acidity(pub i32);
 
-#[derive(Clone, Copy)]
+/// Heat held by a particle, in degrees Celsius.
+///
+/// Heat flows between touching particles that both have a temperature, and between a particle
+/// and the air in empty cells next to it. The particle turns critical once it gets hotter than
+/// `critical_above` or colder than `critical_below`.
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Temperature {
-    pub current_temperature: i32,
-    pub starting_temperature: i32,
-    pub coolable: bool,
-    pub heatable: bool,
-    pub critical_on_cool: bool,
-    pub change_on_critical: Option<ParticleTypes>,
+    pub current_temperature: f32,
+    /// Share of the temperature difference exchanged with a neighbour every tick, from 0 to 1.
+    pub conductivity: f32,
+    /// Heat it takes to change the temperature by one degree.
+    pub heat_capacity: f32,
+    /// Melting or boiling point.
+    pub critical_above: Option<f32>,
+    /// Freezing or solidifying point.
+    pub critical_below: Option<f32>,
+    pub change_on_critical: Option<MaterialId>,
     pub explosion_radius: i32,
 }
 
 impl Temperature {
     pub fn new(
-        starting_temperature: i32,
-        coolable: bool,
-        heatable: bool,
-        critical_on_cool: bool,
-        change_on_critical: Option<ParticleTypes>,
+        starting_temperature: f32,
+        conductivity: f32,
+        heat_capacity: f32,
+        critical_above: Option<f32>,
+        critical_below: Option<f32>,
+        change_on_critical: Option<MaterialId>,
         explosion_radius: i32,
     ) -> Self {
         Self {
-            starting_temperature,
             current_temperature: starting_temperature,
-            coolable,
-            heatable,
-            critical_on_cool,
+            conductivity,
+            heat_capacity,
+            critical_above,
+            critical_below,
             change_on_critical,
             explosion_radius,
         }
     }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical_above
+            .is_some_and(|above| self.current_temperature >= above)
+            || self
+                .critical_below
+                .is_some_and(|below| self.current_temperature <= below)
+    }
+}
+
+/// Temperature of the air in empty cells, which particles exposed to it slowly cool or heat
+/// towards.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ambient {
+    /// In degrees Celsius.
+    pub temperature: f32,
+    /// Conductivity of the air, used instead of a neighbour's for each empty cell.
+    pub conductivity: f32,
 }
 
-#[derive(Clone, Copy)]
-pub struct TemperatureChanger(pub i32);
+impl Default for Ambient {
+    fn default() -> Self {
+        Self {
+            temperature: 20.0,
+            conductivity: 0.01,
+        }
+    }
+}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Burnable {
-    pub burn_temperature: i32,
+    /// Ignition point in degrees Celsius.
+    pub burn_temperature: f32,
+    /// Temperature the fire heats the particle up to while it burns.
+    pub flame_temperature: f32,
     pub burn_ticks: i32,
     pub burn_color: (u8, u8, u8, u8),
     pub cooled_color: (u8, u8, u8, u8),
     pub burning: bool,
 }
 
-#[derive(Clone, Copy)]
+/// Water soaked up by a particle, which darkens it, makes it clump and keeps it from heating up
+/// past boiling until it dries.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Absorbent {
+    /// From 0 when dry to 1 when soaked.
+    pub wetness: f32,
+    /// Chance to soak up each touching water every tick.
+    pub soak_chance: f64,
+    /// Wetness lost every tick for each empty cell next to the particle.
+    pub dry_rate: f32,
+    pub dry_color: (u8, u8, u8, u8),
+    pub wet_color: (u8, u8, u8, u8),
+    /// Friction once soaked, so wet powders hold steeper slopes.
+    pub wet_friction: f32,
+    /// Material it turns into once soaked, like dirt into mud.
+    pub soaked_as: Option<MaterialId>,
+    /// Material it turns into once dry, like mud into dirt.
+    pub dried_as: Option<MaterialId>,
+}
+
+impl Absorbent {
+    /// Blends the dry and wet colours by the wetness.
+    pub fn color(&self) -> (u8, u8, u8, u8) {
+        let blend =
+            |dry: u8, wet: u8| (dry as f32 + (wet as f32 - dry as f32) * self.wetness) as u8;
+        let (dry, wet) = (self.dry_color, self.wet_color);
+
+        (
+            blend(dry.0, wet.0),
+            blend(dry.1, wet.1),
+            blend(dry.2, wet.2),
+            blend(dry.3, wet.3),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Growable {
     pub energy: u32,
     pub spread_chance: f64,
-    pub grow_as: ParticleTypes,
+    pub grow_as: MaterialId,
     pub up_chance: f64,
     pub can_sprout: bool,
 }
 
 impl Growable {
-    pub fn new(energy: u32, spread_chance: f64, up_chance: f64, grow_as: ParticleTypes) -> Self {
+    pub fn new(
+        energy: u32,
+        spread_chance: f64,
+        up_chance: f64,
+        grow_as: MaterialId,
+        rng: &mut impl Rng,
+    ) -> Self {
         Self {
             energy,
             spread_chance,
             grow_as,
             up_chance,
-            can_sprout: thread_rng().gen_bool(up_chance),
+            can_sprout: rng.gen_bool(up_chance),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Default)]
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum MovementType {
     Solid,
     #[default]
@@ -150,7 +310,7 @@ pub enum MovementType {
     Gas,
 }
 
-#[derive(Clone, Copy, PartialEq, Default)]
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum CollisionType {
     #[default]
     None,
@@ -0,0 +1,470 @@
+//! Particle definitions loaded from `.particle.ron` and `.particle.json` assets.
+//!
+//! Each definition registers a material in the [`MaterialRegistry`], replacing the one with the
+//! same name. Definitions are registered again whenever their file changes.
+
+use std::{fmt, io};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    material::{MaterialRegistry, Product},
+    particle::*,
+};
+
+pub struct ParticleDefinitionPlugin {
+    /// Asset paths of the definitions to load.
+    pub paths: Vec<String>,
+}
+
+impl Plugin for ParticleDefinitionPlugin {
+    fn build(&self, app: &mut App) {
+        if self.paths.is_empty() {
+            return;
+        }
+
+        let paths = self.paths.clone();
+        app.init_asset::<ParticleDefinition>()
+            .register_asset_loader(ParticleDefinitionLoader)
+            .add_systems(
+                Startup,
+                move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                    commands.insert_resource(ParticleDefinitionHandles {
+                        _handles: paths.iter().map(|path| asset_server.load(path)).collect(),
+                    });
+                },
+            )
+            .add_systems(Update, register_particle_definitions);
+    }
+}
+
+/// Describes a material, mirroring the fields of [`Particle`].
+///
+/// Other materials are referenced by name and don't need to be registered yet.
+#[derive(Asset, TypePath, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParticleDefinition {
+    pub name: String,
+    pub health: ParticleHealth,
+    pub color: (u8, u8, u8, u8),
+    pub movement_type: MovementType,
+    /// In kg/m³, like 1000 for water. Particles sink through lighter powders, liquids and gases,
+    /// and rise through denser liquids. Solids never move, so theirs doesn't matter. Defaults to
+    /// the highest density, so it sinks through anything.
+    pub density: u32,
+    /// Cells it flows sideways in a single tick when it can't fall or rise. Defaults to 1.
+    pub dispersion: u32,
+    /// Ticks between each sideways flow on average, so higher values ooze. Defaults to 1.
+    pub viscosity: u32,
+    /// Gas pressure next to the particle that breaks it. Never breaks when `None`.
+    pub burst_pressure: Option<f32>,
+    pub acidity: Option<i32>,
+    pub temperature: Option<TemperatureDefinition>,
+    pub burnable: Option<BurnableDefinition>,
+    pub absorbent: Option<AbsorbentDefinition>,
+    pub tick_life: Option<TickLifeDefinition>,
+    pub growable: Option<GrowableDefinition>,
+    pub collision_type: CollisionType,
+    /// Defaults to `true`.
+    pub affected_by_gravity: bool,
+    /// Multiplies the gravity it falls or rises with, so lower values drift. Defaults to 1.
+    pub gravity_scale: f32,
+    /// Fastest it moves in cells per tick. Defaults to 10.
+    pub terminal_velocity: f32,
+    /// Share of its velocity lost to the air every tick, from 0 to 1. Defaults to 0.1.
+    pub air_drag: f32,
+    /// Share of its speed kept when bouncing off what it runs into, from 0 to 1.
+    pub restitution: f32,
+    /// Chance a powder stops sliding once it lands, from 0 to 1. Powders without friction pile
+    /// up at 45°, while higher values hold steeper slopes.
+    pub friction: f32,
+    pub growable_on: bool,
+    /// Reactions with the materials this one touches.
+    pub reactions: Vec<ReactionDefinition>,
+}
+
+impl Default for ParticleDefinition {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            health: default(),
+            color: (255, 255, 255, 255),
+            movement_type: default(),
+            density: u32::MAX,
+            dispersion: 1,
+            viscosity: 1,
+            burst_pressure: None,
+            acidity: None,
+            temperature: None,
+            burnable: None,
+            absorbent: None,
+            tick_life: None,
+            growable: None,
+            collision_type: default(),
+            affected_by_gravity: true,
+            gravity_scale: 1.0,
+            terminal_velocity: 10.0,
+            air_drag: 0.1,
+            restitution: 0.0,
+            friction: 0.0,
+            growable_on: false,
+            reactions: vec![],
+        }
+    }
+}
+
+/// Temperatures are absolute, in degrees Celsius.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemperatureDefinition {
+    pub starting_temperature: f32,
+    /// Share of the temperature difference exchanged with a neighbour every tick, from 0 to 1.
+    pub conductivity: f32,
+    /// Heat it takes to change the temperature by one degree, so higher values heat up and
+    /// cool down slower.
+    pub heat_capacity: f32,
+    /// Turns critical at or above this temperature, like a melting or boiling point.
+    #[serde(default)]
+    pub critical_above: Option<f32>,
+    /// Turns critical at or below this temperature, like a freezing point.
+    #[serde(default)]
+    pub critical_below: Option<f32>,
+    #[serde(default)]
+    pub change_on_critical: Option<String>,
+    #[serde(default)]
+    pub explosion_radius: i32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BurnableDefinition {
+    /// Ignition point in degrees Celsius. Needs a temperature to ever ignite.
+    pub burn_temperature: f32,
+    /// Temperature the fire heats the particle up to while it burns.
+    pub flame_temperature: f32,
+    pub burn_ticks: i32,
+    pub burn_color: (u8, u8, u8, u8),
+    pub cooled_color: (u8, u8, u8, u8),
+}
+
+/// Lets the material soak up touching water. The particle keeps its `color` while dry and shifts
+/// towards `wet_color` as it gets wetter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AbsorbentDefinition {
+    /// Wetness new particles start with, from 0 to 1.
+    #[serde(default)]
+    pub wetness: f32,
+    /// Chance to soak up each touching water every tick, which takes the water away.
+    pub soak_chance: f64,
+    /// Wetness lost every tick for each empty cell next to the particle.
+    pub dry_rate: f32,
+    pub wet_color: (u8, u8, u8, u8),
+    /// Friction once soaked, from 0 to 1. Defaults to the dry friction.
+    #[serde(default)]
+    pub wet_friction: Option<f32>,
+    /// Material it turns into once soaked.
+    #[serde(default)]
+    pub soaked_as: Option<String>,
+    /// Material it turns into once dry.
+    #[serde(default)]
+    pub dried_as: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TickLifeDefinition {
+    /// Range the starting health is picked from, replacing `health.amount`.
+    #[serde(default)]
+    pub lifetime: Option<(i32, i32)>,
+    #[serde(default)]
+    pub replace_on_death: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrowableDefinition {
+    pub energy: u32,
+    pub spread_chance: f64,
+    pub up_chance: f64,
+    pub grow_as: String,
+}
+
+/// Turns a particle and the one it touches into other materials, like water and lava into
+/// steam and stone. It doesn't happen until every material it produces is registered.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReactionDefinition {
+    /// Name of the material this one reacts with.
+    pub with: String,
+    /// Chance to react with each touching particle every tick.
+    pub chance: f64,
+    #[serde(default)]
+    pub becomes: Product<String>,
+    #[serde(default)]
+    pub other_becomes: Product<String>,
+    /// Material created in an empty cell next to the particle.
+    #[serde(default)]
+    pub byproduct: Option<String>,
+}
+
+impl ParticleDefinition {
+    /// Checks the values that would otherwise panic or silently break the simulation.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("name can't be empty".to_string());
+        }
+
+        match self
+            .tick_life
+            .as_ref()
+            .and_then(|tick_life| tick_life.lifetime)
+        {
+            Some((min, max)) if min <= 0 || min >= max => {
+                return Err(format!(
+                    "tick_life lifetime must be a range of positive ticks, got {}..{}",
+                    min, max
+                ));
+            }
+            Some(_) => {}
+            None if self.health.amount <= 0 => {
+                return Err(format!(
+                    "health amount must be positive, got {}",
+                    self.health.amount
+                ));
+            }
+            None => {}
+        }
+
+        if !self.gravity_scale.is_finite() {
+            return Err(format!(
+                "gravity_scale must be finite, got {}",
+                self.gravity_scale
+            ));
+        }
+
+        if !(self.terminal_velocity > 0.0 && self.terminal_velocity.is_finite()) {
+            return Err(format!(
+                "terminal_velocity must be positive, got {}",
+                self.terminal_velocity
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.air_drag) {
+            return Err(format!(
+                "air_drag must be between 0 and 1, got {}",
+                self.air_drag
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.restitution) {
+            return Err(format!(
+                "restitution must be between 0 and 1, got {}",
+                self.restitution
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.friction) {
+            return Err(format!(
+                "friction must be between 0 and 1, got {}",
+                self.friction
+            ));
+        }
+
+        if self.viscosity == 0 {
+            return Err("viscosity must be at least 1".into());
+        }
+
+        if let Some(burst_pressure) = self.burst_pressure {
+            if !(burst_pressure > 0.0 && burst_pressure.is_finite()) {
+                return Err(format!(
+                    "burst_pressure must be positive, got {}",
+                    burst_pressure
+                ));
+            }
+        }
+
+        if let Some(temperature) = &self.temperature {
+            if !(0.0..=1.0).contains(&temperature.conductivity) {
+                return Err(format!(
+                    "temperature conductivity must be between 0 and 1, got {}",
+                    temperature.conductivity
+                ));
+            }
+            if !(temperature.heat_capacity > 0.0 && temperature.heat_capacity.is_finite()) {
+                return Err(format!(
+                    "temperature heat_capacity must be positive, got {}",
+                    temperature.heat_capacity
+                ));
+            }
+            if temperature.explosion_radius < 0 {
+                return Err(format!(
+                    "temperature explosion_radius can't be negative, got {}",
+                    temperature.explosion_radius
+                ));
+            }
+        }
+
+        if let Some(burnable) = self.burnable {
+            if burnable.burn_ticks <= 0 {
+                return Err(format!(
+                    "burnable burn_ticks must be positive, got {}",
+                    burnable.burn_ticks
+                ));
+            }
+        }
+
+        if let Some(absorbent) = &self.absorbent {
+            for (name, value) in [
+                ("wetness", absorbent.wetness as f64),
+                ("soak_chance", absorbent.soak_chance),
+                ("dry_rate", absorbent.dry_rate as f64),
+                (
+                    "wet_friction",
+                    absorbent.wet_friction.unwrap_or_default() as f64,
+                ),
+            ] {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(format!(
+                        "absorbent {} must be between 0 and 1, got {}",
+                        name, value
+                    ));
+                }
+            }
+        }
+
+        if let Some(growable) = &self.growable {
+            for (name, chance) in [
+                ("spread_chance", growable.spread_chance),
+                ("up_chance", growable.up_chance),
+            ] {
+                if !(0.0..=1.0).contains(&chance) {
+                    return Err(format!(
+                        "growable {} must be between 0 and 1, got {}",
+                        name, chance
+                    ));
+                }
+            }
+        }
+
+        for reaction in &self.reactions {
+            if reaction.with.is_empty() {
+                return Err("reaction with can't be empty".to_string());
+            }
+            if !(0.0..=1.0).contains(&reaction.chance) {
+                return Err(format!(
+                    "reaction chance must be between 0 and 1, got {}",
+                    reaction.chance
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps the definition assets loaded.
+#[derive(Resource)]
+struct ParticleDefinitionHandles {
+    _handles: Vec<Handle<ParticleDefinition>>,
+}
+
+#[derive(Default)]
+struct ParticleDefinitionLoader;
+
+impl AssetLoader for ParticleDefinitionLoader {
+    type Asset = ParticleDefinition;
+    type Settings = ();
+    type Error = ParticleDefinitionError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ParticleDefinition, ParticleDefinitionError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        let is_json = load_context
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == "json");
+        let definition: ParticleDefinition = match is_json {
+            true => serde_json::from_slice(&bytes)?,
+            false => ron::de::from_bytes(&bytes)?,
+        };
+
+        definition
+            .validate()
+            .map_err(ParticleDefinitionError::Invalid)?;
+
+        Ok(definition)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["particle.ron", "particle.json"]
+    }
+}
+
+#[derive(Debug)]
+pub enum ParticleDefinitionError {
+    Io(io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ParticleDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read particle definition: {}", error),
+            Self::Ron(error) => write!(f, "malformed RON particle definition at {}", error),
+            Self::Json(error) => write!(f, "malformed JSON particle definition: {}", error),
+            Self::Invalid(reason) => write!(f, "invalid particle definition: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParticleDefinitionError {}
+
+impl From<io::Error> for ParticleDefinitionError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for ParticleDefinitionError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl From<serde_json::Error> for ParticleDefinitionError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+/// Registers a definition whenever its asset finishes loading or changes.
+fn register_particle_definitions(
+    mut events: EventReader<AssetEvent<ParticleDefinition>>,
+    assets: Res<Assets<ParticleDefinition>>,
+    mut registry: ResMut<MaterialRegistry>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(definition) = assets.get(*id) else {
+            continue;
+        };
+
+        let material = registry.register(definition.clone());
+        info!("Registered material {} as {:?}", definition.name, material);
+    }
+}
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 
 use super::{
-    particle_types::{get_particle, ParticleTypes},
+    history::EditHistory,
+    material::MaterialId,
+    replay::{record_checksums, InputRecorder, PlacerInput},
     sandbox::Sandbox,
+    simulation::update_particles,
 };
 
 pub struct ParticlePlacerPlugin;
@@ -10,31 +13,56 @@ pub struct ParticlePlacerPlugin;
 impl Plugin for ParticlePlacerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SelectedParticle {
-            particle_type: ParticleTypes::Sand,
+            material: MaterialId::SAND,
         })
-        .add_systems(Update, place_particles);
+        .add_systems(
+            Update,
+            (
+                (select_material, undo_edits, place_particles)
+                    .chain()
+                    .before(update_particles),
+                record_checksums.after(update_particles),
+            ),
+        );
     }
 }
 
 #[derive(Resource)]
 pub struct SelectedParticle {
-    particle_type: ParticleTypes,
+    material: MaterialId,
 }
 
 pub fn place_particles(
-    mut sandbox_query: Query<&mut Sandbox>,
+    mut sandbox_query: Query<(Entity, &mut Sandbox, &Transform)>,
     query_window: Query<&Window>,
     query_camera: Query<(&Camera, &GlobalTransform)>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut selected: ResMut<SelectedParticle>,
+    selected: Res<SelectedParticle>,
+    mut history: ResMut<EditHistory>,
+    mut recorder: Option<ResMut<InputRecorder>>,
 ) {
-    let (camera, camera_transform) = query_camera.single();
-    let window: &Window = query_window.get_single().unwrap();
-    let mut sandbox = sandbox_query.single_mut();
+    let Ok((camera, camera_transform)) = query_camera.get_single() else {
+        return;
+    };
+    let Ok(window) = query_window.get_single() else {
+        return;
+    };
+
+    let recorded = recorder.as_ref().and_then(|recorder| {
+        let (_, sandbox, _) = sandbox_query.get(recorder.entity()).ok()?;
+        Some((recorder.entity(), sandbox.ticks()))
+    });
+    let mut record = |input| {
+        if let (Some(recorder), Some((_, ticks))) = (&mut recorder, recorded) {
+            recorder.record(ticks, input);
+        }
+    };
 
-    if let Some(particle_type) = set_particle_type(keyboard_input) {
-        selected.particle_type = particle_type;
+    // Everything placed while a button is held is undone as one stroke
+    if !mouse_button_input.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        history.finish();
+        record(PlacerInput::EndStroke);
+        return;
     }
 
     if let Some(world_position) = window
@@ -42,78 +70,156 @@ pub fn place_particles(
         .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
         .map(|ray| ray.origin.truncate())
     {
-        let x = ((world_position.x / 8.0) + (sandbox.width() / 2) as f32) as usize;
-        let y = ((world_position.y / 8.0) + (sandbox.height() / 2) as f32) as usize;
-
-        if sandbox.out_of_bounds_usize(x, y) {
+        // Picks the top-most sandbox when several overlap the cursor
+        let target = sandbox_query
+            .iter_mut()
+            .filter_map(|(entity, sandbox, transform)| {
+                let position = world_to_sandbox(&sandbox, transform, world_position)?;
+                Some((entity, sandbox, transform.translation.z, position))
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        let Some((entity, mut sandbox, _, (x, y))) = target else {
             return;
+        };
+
+        let input = PlacerInput::Brush {
+            x,
+            y,
+            material: selected.material,
+            place: mouse_button_input.pressed(MouseButton::Left),
+            erase: mouse_button_input.pressed(MouseButton::Right),
+        };
+        input.apply(entity, &mut sandbox, &mut history);
+
+        // Painting another sandbox ends the recorded one's stroke
+        match recorded.is_some_and(|(recorded, _)| recorded == entity) {
+            true => record(input),
+            false => record(PlacerInput::EndStroke),
         }
+    }
+}
+
+pub fn select_material(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<SelectedParticle>,
+) {
+    if let Some(material) = set_material(keyboard_input) {
+        selected.material = material;
+    }
+}
 
-        for x_offset in -5..5 {
-            for y_offset in -5..5 {
-                let x = x.saturating_add_signed(x_offset);
-                let y = y.saturating_add_signed(y_offset);
-
-                if mouse_button_input.pressed(MouseButton::Left) && sandbox.get(x, y).is_none() {
-                    sandbox.set(x, y, Some(get_particle(selected.particle_type)));
-                } else if mouse_button_input.pressed(MouseButton::Right)
-                    && sandbox.get(x, y).is_some()
-                {
-                    sandbox.set(x, y, None);
-                }
+/// Undoes the latest edit with Ctrl+Z and redoes it with Ctrl+Y.
+pub fn undo_edits(
+    mut sandbox_query: Query<(Entity, &mut Sandbox)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<EditHistory>,
+    recorder: Option<ResMut<InputRecorder>>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    let (input, edit) = if keyboard_input.just_pressed(KeyCode::KeyZ) {
+        (PlacerInput::Undo, history.undo())
+    } else if keyboard_input.just_pressed(KeyCode::KeyY) {
+        (PlacerInput::Redo, history.redo())
+    } else {
+        return;
+    };
+    let entity = edit.map(|edit| edit.entity);
+
+    if let Some(edit) = edit {
+        if let Ok((_, mut sandbox)) = sandbox_query.get_mut(edit.entity) {
+            match input {
+                PlacerInput::Undo => edit.revert(&mut sandbox),
+                _ => edit.apply(&mut sandbox),
             }
         }
     }
+
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let Ok((_, sandbox)) = sandbox_query.get(recorder.entity()) else {
+        return;
+    };
+
+    // Edits of other sandboxes still end the recorded sandbox's stroke
+    let ticks = sandbox.ticks();
+    match entity.is_none() || entity == Some(recorder.entity()) {
+        true => recorder.record(ticks, input),
+        false => recorder.record(ticks, PlacerInput::EndStroke),
+    }
+}
+
+/// Converts a world position into the cell of the sandbox it lands on.
+pub fn world_to_sandbox(
+    sandbox: &Sandbox,
+    transform: &Transform,
+    world_position: Vec2,
+) -> Option<(usize, usize)> {
+    let local = (world_position - transform.translation.truncate()) / transform.scale.truncate()
+        + Vec2::new((sandbox.width() / 2) as f32, (sandbox.height() / 2) as f32);
+    if local.x < 0.0 || local.y < 0.0 {
+        return None;
+    }
+
+    let (x, y) = (local.x as usize, local.y as usize);
+    if sandbox.out_of_bounds_usize(x, y) {
+        return None;
+    }
+
+    Some((x, y))
 }
 
-fn set_particle_type(keyboard_input: Res<ButtonInput<KeyCode>>) -> Option<ParticleTypes> {
+fn set_material(keyboard_input: Res<ButtonInput<KeyCode>>) -> Option<MaterialId> {
     if keyboard_input.just_pressed(KeyCode::Digit1) {
         println!("Chose Sand");
-        return Some(ParticleTypes::Sand);
+        return Some(MaterialId::SAND);
     }
     if keyboard_input.just_pressed(KeyCode::Digit2) {
         println!("Chose Water");
-        return Some(ParticleTypes::Water);
+        return Some(MaterialId::WATER);
     }
     if keyboard_input.just_pressed(KeyCode::Digit3) {
         println!("Chose Stone");
-        return Some(ParticleTypes::Stone);
+        return Some(MaterialId::STONE);
     }
     if keyboard_input.just_pressed(KeyCode::Digit4) {
         println!("Chose Acid");
-        return Some(ParticleTypes::Acid);
+        return Some(MaterialId::ACID);
     }
     if keyboard_input.just_pressed(KeyCode::Digit5) {
         println!("Chose Wood");
-        return Some(ParticleTypes::Wood);
+        return Some(MaterialId::WOOD);
     }
     if keyboard_input.just_pressed(KeyCode::Digit6) {
         println!("Chose Spark");
-        return Some(ParticleTypes::Spark);
+        return Some(MaterialId::SPARK);
     }
     if keyboard_input.just_pressed(KeyCode::Digit7) {
         println!("Chose Lava");
-        return Some(ParticleTypes::Lava);
+        return Some(MaterialId::LAVA);
     }
     if keyboard_input.just_pressed(KeyCode::Digit8) {
         println!("Chose Oil");
-        return Some(ParticleTypes::Oil);
+        return Some(MaterialId::OIL);
     }
     if keyboard_input.just_pressed(KeyCode::Digit9) {
         println!("Chose Gunpowder");
-        return Some(ParticleTypes::Gunpowder);
+        return Some(MaterialId::GUNPOWDER);
     }
     if keyboard_input.just_pressed(KeyCode::Digit0) {
         println!("Chose TNT");
-        return Some(ParticleTypes::Tnt);
+        return Some(MaterialId::TNT);
     }
     if keyboard_input.just_pressed(KeyCode::Minus) {
         println!("Chose Dirt");
-        return Some(ParticleTypes::Dirt);
+        return Some(MaterialId::DIRT);
     }
     if keyboard_input.just_pressed(KeyCode::Equal) {
         println!("Chose Grass");
-        return Some(ParticleTypes::Grass);
+        return Some(MaterialId::GRASS);
     }
 
     None
@@ -0,0 +1,253 @@
+//! Gas pressure, which gases build up in the cells they fill and which pushes particles from
+//! high to low pressure.
+//!
+//! Pressure spreads between neighbouring empty and gas cells and slowly leaks away, so gases in
+//! a sealed container keep building it up while gases in the open spread it out. The edges of
+//! the sandbox are open air, which the pressure vents into. Particles with
+//! a `burst_pressure` break once the pressure next to them gets too high.
+
+use super::{particle::MovementType, sandbox::Sandbox};
+
+/// Pressure every gas particle adds to its cell each tick.
+const GAS_PRESSURE: f32 = 1.0;
+
+/// Share of the pressure difference that flows to each open neighbour every tick.
+const DIFFUSION: f32 = 0.2;
+
+/// Share of the pressure that leaks away every tick, which limits a sealed container full of
+/// gas to `GAS_PRESSURE / LEAK`.
+const LEAK: f32 = 0.02;
+
+/// Pressure below this is dropped, so empty areas go back to exactly 0.
+const MIN_PRESSURE: f32 = 0.001;
+
+/// Velocity gained per unit of pressure difference between the two sides of a particle.
+const PUSH_PER_PRESSURE: f32 = 0.2;
+
+/// Pressure difference it takes to move particles that aren't gases.
+const PUSH_THRESHOLD: f32 = 25.0;
+
+/// Most velocity pressure can add in a single tick.
+const MAX_PUSH: f32 = 10.0;
+
+/// Pressure an explosion adds to every cell it covers, per cell of radius.
+pub(crate) const EXPLOSION_PRESSURE: f32 = 10.0;
+
+/// Spreads, leaks and builds up the pressure in the chunks holding gas or pressure and the
+/// chunks next to them, then breaks the particles that can't hold the pressure next to them.
+///
+/// Every chunk spreads the pressure it had before the update, so the chunks can be updated in
+/// any order. Chunks far from gases and explosions aren't looked at.
+pub(crate) fn update_pressure(sandbox: &mut Sandbox) {
+    let updated = pressurized_chunks(sandbox);
+    if updated.is_empty() {
+        return;
+    }
+
+    let next = updated
+        .iter()
+        .map(|&index| spread(index, sandbox))
+        .collect::<Vec<_>>();
+
+    let mut changed = vec![];
+    for (&index, pressure) in updated.iter().zip(next) {
+        let (offset_x, offset_y) = chunk_offset(index, sandbox);
+        let chunk = sandbox.get_chunk_mut(offset_x, offset_y);
+        for (i, &pressure) in pressure.iter().enumerate() {
+            let (x, y) = (i % chunk.width(), i / chunk.width());
+            let previous = chunk.pressure(x, y);
+            if pressure != previous && pressure.max(previous) > PUSH_THRESHOLD {
+                changed.push((offset_x + x, offset_y + y));
+            }
+        }
+        chunk.set_pressures(pressure);
+    }
+
+    for &index in &updated {
+        burst(index, sandbox);
+    }
+
+    // Particles only react to the pressure while they're simulated. Gases keep moving anyway,
+    // and nothing else is pushed by less than the threshold.
+    for (x, y) in changed {
+        for (neighbor_x, neighbor_y) in neighbors(x, y) {
+            sandbox.weak_tick(neighbor_x, neighbor_y);
+        }
+    }
+}
+
+/// Returns the indices of the chunks holding pressure or a gas that moved, along with the
+/// chunks next to them that the pressure can spread to.
+fn pressurized_chunks(sandbox: &Sandbox) -> Vec<usize> {
+    let (x_chunks, y_chunks) = (sandbox.x_chunks(), sandbox.y_chunks());
+
+    // Gases add pressure every tick, so resting ones are in chunks that already have some
+    let mut updated = vec![false; x_chunks * y_chunks];
+    for (index, chunk) in sandbox.get_all_chunks().iter().enumerate() {
+        let has_gas = || {
+            chunk.dirty_rect().is_some_and(|rect| {
+                rect.iter().any(|(x, y)| {
+                    chunk
+                        .get(x, y)
+                        .is_some_and(|particle| particle.movement_type == MovementType::Gas)
+                })
+            })
+        };
+        if !chunk.has_pressure() && !has_gas() {
+            continue;
+        }
+
+        let (chunk_x, chunk_y) = (index % x_chunks, index / x_chunks);
+        updated[index] = true;
+        if chunk_x > 0 {
+            updated[index - 1] = true;
+        }
+        if chunk_x + 1 < x_chunks {
+            updated[index + 1] = true;
+        }
+        if chunk_y > 0 {
+            updated[index - x_chunks] = true;
+        }
+        if chunk_y + 1 < y_chunks {
+            updated[index + x_chunks] = true;
+        }
+    }
+
+    (0..updated.len()).filter(|&index| updated[index]).collect()
+}
+
+/// Returns the next pressure of every cell in the chunk.
+fn spread(index: usize, sandbox: &Sandbox) -> Vec<f32> {
+    let chunk = &sandbox.get_all_chunks()[index];
+    let (offset_x, offset_y) = chunk_offset(index, sandbox);
+
+    let mut next = vec![0.0; chunk.width() * chunk.height()];
+    for (i, particle) in chunk.particles().iter().enumerate() {
+        let movement_type = particle.map(|particle| particle.movement_type);
+        if !is_open(movement_type) {
+            continue;
+        }
+
+        let (x, y) = (i % chunk.width(), i / chunk.width());
+        let own = chunk.pressure(x, y);
+        let mut pressure = own;
+        for (neighbor_x, neighbor_y) in neighbors(offset_x + x, offset_y + y) {
+            if let Some(neighbor) = open_pressure(neighbor_x, neighbor_y, sandbox) {
+                pressure += (neighbor - own) * DIFFUSION;
+            }
+        }
+        pressure *= 1.0 - LEAK;
+        if movement_type.is_some() {
+            pressure += GAS_PRESSURE;
+        }
+
+        if pressure >= MIN_PRESSURE {
+            next[i] = pressure;
+        }
+    }
+
+    next
+}
+
+/// Breaks the particles of the chunk that border a cell with more than their `burst_pressure`.
+fn burst(index: usize, sandbox: &mut Sandbox) {
+    let chunk = &sandbox.get_all_chunks()[index];
+    let (offset_x, offset_y) = chunk_offset(index, sandbox);
+    let bursting = chunk
+        .particles()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, particle)| {
+            let burst_pressure = particle.and_then(|particle| particle.burst_pressure)?;
+            Some((
+                offset_x + i % chunk.width(),
+                offset_y + i / chunk.width(),
+                burst_pressure,
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    for (x, y, burst_pressure) in bursting {
+        if neighbors(x, y).into_iter().any(|(neighbor_x, neighbor_y)| {
+            sandbox.pressure(neighbor_x, neighbor_y) >= burst_pressure
+        }) {
+            sandbox.set(x, y, None);
+        }
+    }
+}
+
+/// Velocity the pressure around the particle pushes it with, from high to low pressure.
+pub(crate) fn pressure_push(x: usize, y: usize, sandbox: &Sandbox) -> (f32, f32) {
+    let movement_type = sandbox
+        .get(x, y)
+        .expect("Only particles are pushed")
+        .movement_type;
+    let [left, right, down, up] = neighbors(x, y).map(|(x, y)| open_pressure(x, y, sandbox));
+
+    push(
+        movement_type,
+        sandbox.pressure(x, y),
+        [left, right, down, up],
+    )
+}
+
+/// Pushes a particle away from the higher of the pressures on its left, right, bottom and top
+/// side, where `None` is a wall.
+///
+/// Gases follow any difference, while other loose particles only move once the difference
+/// gets large, like next to an explosion. Solids never move.
+fn push(movement_type: MovementType, own: f32, sides: [Option<f32>; 4]) -> (f32, f32) {
+    let threshold = match movement_type {
+        MovementType::Gas => 0.0,
+        MovementType::Powder | MovementType::Liquid => PUSH_THRESHOLD,
+        MovementType::Solid => return (0.0, 0.0),
+    };
+
+    // Walls push back with the particle's own pressure
+    let [left, right, down, up] = sides.map(|side| side.unwrap_or(own));
+    let push = |difference: f32| {
+        let excess = difference.abs() - threshold;
+        if excess <= 0.0 {
+            return 0.0;
+        }
+
+        (excess * PUSH_PER_PRESSURE).min(MAX_PUSH) * difference.signum()
+    };
+
+    (push(left - right), push(down - up))
+}
+
+/// Whether gas and pressure can pass through a cell holding a particle that moves like this.
+fn is_open(movement_type: Option<MovementType>) -> bool {
+    matches!(movement_type, None | Some(MovementType::Gas))
+}
+
+/// Returns the pressure of the cell when gas and pressure can pass through it, where the
+/// outside of the sandbox is open air without any pressure.
+fn open_pressure(x: usize, y: usize, sandbox: &Sandbox) -> Option<f32> {
+    if sandbox.out_of_bounds_usize(x, y) {
+        return Some(0.0);
+    }
+
+    let movement_type = sandbox.get(x, y).map(|particle| particle.movement_type);
+    is_open(movement_type).then(|| sandbox.pressure(x, y))
+}
+
+/// Returns the cells on the left, right, bottom and top of the cell, which may be outside of
+/// the sandbox.
+fn neighbors(x: usize, y: usize) -> [(usize, usize); 4] {
+    [
+        (x.overflowing_sub(1).0, y),
+        (x + 1, y),
+        (x, y.overflowing_sub(1).0),
+        (x, y + 1),
+    ]
+}
+
+/// Returns the position of the bottom left cell of the chunk.
+fn chunk_offset(index: usize, sandbox: &Sandbox) -> (usize, usize) {
+    (
+        index % sandbox.x_chunks() * sandbox.chunk_width(),
+        index / sandbox.x_chunks() * sandbox.chunk_height(),
+    )
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 
 use super::sandbox::Sandbox;
@@ -8,32 +10,69 @@ const BACKGROUND_COLOR: (u8, u8, u8, u8) = (0, 0, 0, 0);
 // Based on https://github.com/grunnt/falling-rust/blob/master/src/render.rs
 pub fn render_particles(
     mut images: ResMut<Assets<Image>>,
-    mut sandbox: Query<(&mut Sandbox, &Handle<Image>)>,
+    sandbox: Query<(Entity, &Sandbox, &Handle<Image>)>,
+    mut flying_cells: Local<HashMap<Entity, Vec<(usize, usize)>>>,
 ) {
-    let (mut sandbox, image_handle) = sandbox
-        .get_single_mut()
-        .expect("Sandbox should be created by this point");
-
-    let image = images.get_mut(image_handle).unwrap();
-    for y in 0..sandbox.height() {
-        for x in 0..sandbox.width() {
-            if !sandbox.get_chunk(x, y).is_strong_ticked() {
+    for (entity, sandbox, image_handle) in &sandbox {
+        let Some(image) = images.get_mut(image_handle) else {
+            continue;
+        };
+
+        // Cells flying particles were drawn over show the grid again
+        let drawn = flying_cells.entry(entity).or_default();
+        for (x, y) in drawn.drain(..) {
+            let color = sandbox
+                .checked_get(x, y)
+                .map_or(BACKGROUND_COLOR, |particle| particle.color);
+            draw_cell(sandbox, image, x, y, color);
+        }
+
+        draw_sandbox(sandbox, image);
+
+        for flying in sandbox.flying() {
+            let (x, y) = flying.cell();
+            if sandbox.out_of_bounds_i32(x, y) {
                 continue;
             }
 
-            let particle = sandbox.get_mut(x, y);
-            let color = match particle {
+            draw_cell(
+                sandbox,
+                image,
+                x as usize,
+                y as usize,
+                flying.particle.color,
+            );
+            drawn.push((x as usize, y as usize));
+        }
+    }
+}
+
+fn draw_sandbox(sandbox: &Sandbox, image: &mut Image) {
+    for chunk in sandbox.get_all_chunks() {
+        // Only cells that changed recently need to be redrawn
+        let Some(rect) = chunk.strong_dirty_rect() else {
+            continue;
+        };
+
+        let offset_x = chunk.local_position.0 * chunk.width();
+        let offset_y = chunk.local_position.1 * chunk.height();
+        for (x, y) in rect.iter() {
+            let color = match chunk.get(x, y) {
                 Some(particle) => particle.color,
                 None => BACKGROUND_COLOR,
             };
 
-            let bytes_per_pixel = 4;
-            let index = (x + y * sandbox.width()) * bytes_per_pixel;
-
-            image.data[index] = color.0;
-            image.data[index + 1] = color.1;
-            image.data[index + 2] = color.2;
-            image.data[index + 3] = color.3;
+            draw_cell(sandbox, image, offset_x + x, offset_y + y, color);
         }
     }
 }
+
+fn draw_cell(sandbox: &Sandbox, image: &mut Image, x: usize, y: usize, color: (u8, u8, u8, u8)) {
+    let bytes_per_pixel = 4;
+    let index = (x + y * sandbox.width()) * bytes_per_pixel;
+
+    image.data[index] = color.0;
+    image.data[index + 1] = color.1;
+    image.data[index + 2] = color.2;
+    image.data[index + 3] = color.3;
+}
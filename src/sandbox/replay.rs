@@ -0,0 +1,399 @@
+//! Records what the particle placer does to a [`Sandbox`] so it can be replayed headlessly.
+//!
+//! A [`Replay`] starts from a save of the sandbox, followed by every [`PlacerInput`] and the
+//! tick it was applied on. A checksum of the sandbox is recorded after every tick, so a replay
+//! can tell exactly which tick stopped matching the recording.
+
+use std::{
+    fmt,
+    hash::Hasher,
+    io::{self, Read, Write},
+};
+
+use bevy::prelude::*;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    history::EditHistory,
+    material::{MaterialId, MaterialRegistry},
+    sandbox::Sandbox,
+    save::{invalid_data, SaveCompression},
+    simulation::{step_sandbox, SweepStrategy},
+    SandboxConfig,
+};
+
+/// Bytes every replay starts with.
+pub const REPLAY_MAGIC: [u8; 4] = *b"FSRP";
+
+/// Version of the format written by [`Replay::save_to`].
+pub const REPLAY_VERSION: u16 = 1;
+
+/// Half the width of the square the brush paints.
+const BRUSH_RADIUS: isize = 5;
+
+/// Something the particle placer did to a sandbox.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PlacerInput {
+    /// Paints the empty cells around the position with the material and erases the others,
+    /// depending on which buttons were held.
+    Brush {
+        x: usize,
+        y: usize,
+        material: MaterialId,
+        place: bool,
+        erase: bool,
+    },
+    /// The buttons were released, ending the stroke in the [`EditHistory`].
+    EndStroke,
+    Undo,
+    Redo,
+}
+
+impl PlacerInput {
+    /// Applies the input to the sandbox on `entity`, recording the changes in the history.
+    pub fn apply(self, entity: Entity, sandbox: &mut Sandbox, history: &mut EditHistory) {
+        match self {
+            Self::Brush {
+                x,
+                y,
+                material,
+                place,
+                erase,
+            } => {
+                for x_offset in -BRUSH_RADIUS..BRUSH_RADIUS {
+                    for y_offset in -BRUSH_RADIUS..BRUSH_RADIUS {
+                        let x = x.saturating_add_signed(x_offset);
+                        let y = y.saturating_add_signed(y_offset);
+                        if sandbox.out_of_bounds_usize(x, y) {
+                            continue;
+                        }
+
+                        if place && sandbox.get(x, y).is_none() {
+                            let particle = sandbox.create_particle(material, x, y);
+                            sandbox.set(x, y, particle);
+                            history.record(entity, x, y, None, particle);
+                        } else if erase && sandbox.get(x, y).is_some() {
+                            let before = sandbox.get(x, y).copied();
+                            sandbox.set(x, y, None);
+                            history.record(entity, x, y, before, None);
+                        }
+                    }
+                }
+            }
+            Self::EndStroke => history.finish(),
+            Self::Undo => {
+                if let Some(edit) = history.undo() {
+                    edit.revert(sandbox);
+                }
+            }
+            Self::Redo => {
+                if let Some(edit) = history.redo() {
+                    edit.apply(sandbox);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct RecordedInput {
+    /// Value of [`Sandbox::ticks`] when the input was applied, before that tick was simulated.
+    tick: u64,
+    input: PlacerInput,
+}
+
+/// Inputs recorded from a sandbox, along with the state they were applied to.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    sweep: SweepStrategy,
+    /// The sandbox when the recording started, written by [`Sandbox::save_to`].
+    initial: Vec<u8>,
+    /// Material names by id when the recording started, so brushes paint the same materials
+    /// after others were registered.
+    materials: Vec<String>,
+    inputs: Vec<RecordedInput>,
+    /// Value of [`Sandbox::checksum`] after each simulated tick.
+    checksums: Vec<(u64, u64)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// A brush paints a material that isn't registered.
+    MissingMaterial(String),
+    /// The sandbox didn't match the recording after the tick.
+    Desync {
+        tick: u64,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read the replay's sandbox: {}", error),
+            Self::MissingMaterial(name) => write!(
+                f,
+                "replay uses the material {} which isn't registered",
+                name
+            ),
+            Self::Desync {
+                tick,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "replay desynced on tick {}: expected checksum {:016x}, got {:016x}",
+                tick, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl Replay {
+    /// Number of ticks simulated during the recording.
+    pub fn ticks(&self) -> usize {
+        self.checksums.len()
+    }
+
+    /// Simulates the recording from its initial sandbox, checking the sandbox after every tick.
+    ///
+    /// `materials` must match the ones used while recording. Returns the sandbox as it was
+    /// when the recording stopped.
+    pub fn run(&self, materials: &MaterialRegistry) -> Result<Sandbox, ReplayError> {
+        let mut sandbox = Sandbox::load_from(&self.initial[..], materials)?;
+        let recorded_inputs = self.map_materials(materials)?;
+        let mut history = EditHistory::default();
+        let entity = Entity::PLACEHOLDER;
+        let config = SandboxConfig {
+            sweep: self.sweep,
+            ..default()
+        };
+
+        let mut inputs = recorded_inputs.iter().peekable();
+        for &(tick, expected) in &self.checksums {
+            while sandbox.ticks() < tick {
+                while let Some(recorded) = inputs.next_if(|input| input.tick <= sandbox.ticks()) {
+                    recorded.input.apply(entity, &mut sandbox, &mut history);
+                }
+                step_sandbox(&mut sandbox, &config);
+            }
+
+            let actual = sandbox.checksum();
+            if actual != expected {
+                return Err(ReplayError::Desync {
+                    tick,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        // Inputs made after the last tick but before the recording stopped
+        for recorded in inputs {
+            recorded.input.apply(entity, &mut sandbox, &mut history);
+        }
+
+        Ok(sandbox)
+    }
+
+    /// Returns the inputs with their materials matched up by name with `materials`.
+    fn map_materials(
+        &self,
+        materials: &MaterialRegistry,
+    ) -> Result<Vec<RecordedInput>, ReplayError> {
+        let ids = self
+            .materials
+            .iter()
+            .map(|name| materials.id(name))
+            .collect::<Vec<_>>();
+
+        let mut inputs = self.inputs.clone();
+        for recorded in inputs.iter_mut() {
+            let PlacerInput::Brush { material, .. } = &mut recorded.input else {
+                continue;
+            };
+            let index = material.index();
+            let Some(id) = ids.get(index).copied().flatten() else {
+                let name = self.materials.get(index).map_or("?", |name| name);
+                return Err(ReplayError::MissingMaterial(name.to_string()));
+            };
+            *material = id;
+        }
+
+        Ok(inputs)
+    }
+
+    pub fn save_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+
+        let mut encoder = ZlibEncoder::new(&mut writer, flate2::Compression::default());
+        bincode::serialize_into(&mut encoder, self).map_err(invalid_data)?;
+        encoder.finish()?;
+
+        writer.flush()
+    }
+
+    pub fn load_from(mut reader: impl Read) -> io::Result<Replay> {
+        let mut header = [0; 6];
+        reader.read_exact(&mut header)?;
+        if header[..4] != REPLAY_MAGIC {
+            return Err(invalid_data("not a sandbox replay"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != REPLAY_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported replay version {}, expected {}",
+                version, REPLAY_VERSION
+            )));
+        }
+
+        bincode::deserialize_from(ZlibDecoder::new(reader)).map_err(invalid_data)
+    }
+}
+
+/// Records the placer inputs of one sandbox while the resource exists.
+///
+/// Checksums are recorded by [`record_checksums`], which has to run right after
+/// [`update_particles`](super::simulation::update_particles).
+#[derive(Resource)]
+pub struct InputRecorder {
+    entity: Entity,
+    start_tick: u64,
+    replay: Replay,
+}
+
+impl InputRecorder {
+    /// Starts recording the sandbox on `entity`.
+    ///
+    /// The sandbox is reloaded from the save the replay starts from, which restarts its random
+    /// number generators so the replay can follow it exactly. Its edits are dropped from the
+    /// history, since the replay starts with an empty one and couldn't undo them.
+    pub fn start(
+        entity: Entity,
+        sandbox: &mut Sandbox,
+        history: &mut EditHistory,
+        sweep: SweepStrategy,
+    ) -> io::Result<Self> {
+        let mut initial = vec![];
+        sandbox.save_to(&mut initial, SaveCompression::None)?;
+        *sandbox = Sandbox::load_from(&initial[..], sandbox.materials())?;
+        history.forget(entity);
+
+        Ok(Self {
+            entity,
+            start_tick: sandbox.ticks(),
+            replay: Replay {
+                sweep,
+                initial,
+                materials: sandbox.materials().names().map(str::to_string).collect(),
+                inputs: vec![],
+                checksums: vec![],
+            },
+        })
+    }
+
+    /// Entity of the sandbox being recorded.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Records an input applied to the sandbox when it had simulated `tick` ticks.
+    pub fn record(&mut self, tick: u64, input: PlacerInput) {
+        // Only the first release matters, and the placer reports it every frame
+        let last = self.replay.inputs.last().map(|recorded| recorded.input);
+        if input == PlacerInput::EndStroke && matches!(last, None | Some(PlacerInput::EndStroke)) {
+            return;
+        }
+
+        self.replay.inputs.push(RecordedInput { tick, input });
+    }
+
+    /// Stores a checksum of the sandbox when it simulated a tick since the last one.
+    pub fn record_checksum(&mut self, sandbox: &Sandbox) {
+        let last = self
+            .replay
+            .checksums
+            .last()
+            .map_or(self.start_tick, |(tick, _)| *tick);
+        if sandbox.ticks() > last {
+            self.replay
+                .checksums
+                .push((sandbox.ticks(), sandbox.checksum()));
+        }
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// Stores a checksum of the recorded sandbox whenever a tick was simulated.
+pub fn record_checksums(recorder: Option<ResMut<InputRecorder>>, sandbox_query: Query<&Sandbox>) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let Ok(sandbox) = sandbox_query.get(recorder.entity) else {
+        return;
+    };
+
+    recorder.record_checksum(sandbox);
+}
+
+impl Sandbox {
+    /// Hashes the tick count and every particle, so two sandboxes with the same checksum
+    /// almost certainly hold the same state.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        hasher.write_u64(self.ticks());
+        for chunk in self.get_all_chunks() {
+            bincode::serialize_into(&mut hasher, chunk.particles()).expect("Hashing can't fail");
+        }
+        bincode::serialize_into(&mut hasher, self.flying()).expect("Hashing can't fail");
+
+        hasher.finish()
+    }
+}
+
+/// FNV-1a, which unlike the standard library's hasher is the same on every platform and version.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Write for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        Hasher::write(self, bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
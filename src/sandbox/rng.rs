@@ -0,0 +1,27 @@
+use bevy::math::IVec2;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Random number generator used by the simulation.
+///
+/// Every chunk owns its own stream, so the same seed and the same inputs always
+/// produce the same sandbox.
+pub type SandboxRng = StdRng;
+
+/// Creates the stream for the chunk at `index` from the sandbox's seed.
+pub fn chunk_rng(seed: u64, index: usize) -> SandboxRng {
+    SandboxRng::seed_from_u64(mix(seed, index as u64))
+}
+
+/// Creates the stream for the chunk with the world `key` of a streamed sandbox.
+pub fn chunk_rng_at(seed: u64, key: IVec2) -> SandboxRng {
+    let stream = ((key.x as u32 as u64) << 32) | key.y as u32 as u64;
+    SandboxRng::seed_from_u64(mix(!seed, stream))
+}
+
+// SplitMix64 finalizer so neighboring chunks don't get correlated seeds
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -1,6 +1,12 @@
 use bevy::prelude::Component;
 
-use super::{chunk::SandboxChunk, particle::Particle};
+use super::{
+    chunk::SandboxChunk,
+    flying::FlyingParticle,
+    material::{MaterialId, MaterialRegistry},
+    particle::{Ambient, Particle},
+    rng::{chunk_rng, SandboxRng},
+};
 
 #[derive(Component)]
 pub struct Sandbox {
@@ -8,30 +14,127 @@ pub struct Sandbox {
     y_chunks: usize,
     chunk_width: usize,
     chunk_height: usize,
+    chunk_offset: (usize, usize),
     total_width: usize,
     total_height: usize,
+    seed: u64,
+    ticks: u64,
     chunks: Vec<SandboxChunk>,
+    flying: Vec<FlyingParticle>,
+    materials: MaterialRegistry,
+    ambient: Ambient,
+    deferred_ticks: Option<DeferredTicks>,
+}
+
+/// Ticks a view couldn't apply because they landed outside of it.
+#[derive(Default)]
+pub(crate) struct DeferredTicks {
+    pub strong: Vec<(usize, usize)>,
+    pub weak: Vec<(usize, usize)>,
+    /// Position and radius of the explosions that reach outside of the view.
+    pub explosions: Vec<(usize, usize, i32)>,
 }
 
 impl Sandbox {
-    pub fn new(x_chunks: usize, y_chunks: usize, chunk_width: usize, chunk_height: usize) -> Self {
+    pub fn new(
+        x_chunks: usize,
+        y_chunks: usize,
+        chunk_width: usize,
+        chunk_height: usize,
+        seed: u64,
+    ) -> Self {
         Self {
             x_chunks,
             y_chunks,
             chunk_width,
             chunk_height,
+            chunk_offset: (0, 0),
             total_width: x_chunks * chunk_width,
             total_height: y_chunks * chunk_height,
+            seed,
+            ticks: 0,
             chunks: {
                 let mut chunks = Vec::with_capacity(x_chunks * y_chunks);
                 for i in 0..chunks.capacity() {
-                    chunks.push(SandboxChunk::new(chunk_width, chunk_height, i, x_chunks));
+                    chunks.push(SandboxChunk::new(
+                        chunk_width,
+                        chunk_height,
+                        (i % x_chunks, i / x_chunks),
+                        chunk_rng(seed, i),
+                    ));
                 }
                 chunks
             },
+            flying: vec![],
+            materials: MaterialRegistry::default(),
+            ambient: Ambient::default(),
+            deferred_ticks: None,
+        }
+    }
+
+    /// Creates a sandbox out of part of this one's chunks, given in row-major order.
+    ///
+    /// The view keeps the same coordinates, treats everything outside of it as out of bounds
+    /// and collects ticks that land outside of it so they can be applied afterwards.
+    pub(crate) fn view(
+        &self,
+        chunk_offset: (usize, usize),
+        x_chunks: usize,
+        y_chunks: usize,
+        chunks: Vec<SandboxChunk>,
+    ) -> Self {
+        Self {
+            x_chunks,
+            y_chunks,
+            chunk_width: self.chunk_width,
+            chunk_height: self.chunk_height,
+            chunk_offset,
+            total_width: x_chunks * self.chunk_width,
+            total_height: y_chunks * self.chunk_height,
+            seed: self.seed,
+            ticks: self.ticks,
+            chunks,
+            flying: vec![],
+            materials: self.materials.clone(),
+            ambient: self.ambient,
+            deferred_ticks: Some(DeferredTicks::default()),
         }
     }
 
+    /// Hands back the chunks of a view along with the ticks it couldn't apply and the particles
+    /// launched in it.
+    pub(crate) fn into_view_parts(self) -> (Vec<SandboxChunk>, DeferredTicks, Vec<FlyingParticle>) {
+        (
+            self.chunks,
+            self.deferred_ticks.unwrap_or_default(),
+            self.flying,
+        )
+    }
+
+    /// Puts chunks taken with [`Sandbox::take_chunks`] back without waking them.
+    pub(crate) fn restore_chunks(&mut self, chunks: Vec<SandboxChunk>) {
+        assert_eq!(
+            chunks.len(),
+            self.x_chunks * self.y_chunks,
+            "Chunk count doesn't match the sandbox"
+        );
+        self.chunks = chunks;
+    }
+
+    /// Marks a cell as changed without it being set.
+    pub fn strong_tick(&mut self, x: usize, y: usize) {
+        if self.out_of_bounds_usize(x, y) {
+            if let Some(deferred) = &mut self.deferred_ticks {
+                deferred.strong.push((x, y));
+            }
+            return;
+        }
+
+        let (chunk_width, chunk_height) = (self.chunk_width, self.chunk_height);
+        self.get_chunk_mut(x, y)
+            .strong_tick_at(x % chunk_width, y % chunk_height);
+    }
+
     pub fn get(&self, x: usize, y: usize) -> Option<&Particle> {
         let index = self.to_index(x, y);
         self.chunks
@@ -89,6 +192,26 @@ impl Sandbox {
         self.strong_tick_neighbors(x, y);
     }
 
+    /// Returns the gas pressure of the cell, which is 0 outside of the sandbox.
+    pub fn pressure(&self, x: usize, y: usize) -> f32 {
+        let index = self.to_index(x, y);
+        self.chunks.get(index).map_or(0.0, |chunk| {
+            chunk.pressure(x % self.chunk_width, y % self.chunk_height)
+        })
+    }
+
+    pub fn set_pressure(&mut self, x: usize, y: usize, pressure: f32) {
+        let index = self.to_index(x, y);
+        if let Some(chunk) = self.chunks.get_mut(index) {
+            chunk.set_pressure(x % self.chunk_width, y % self.chunk_height, pressure);
+        }
+    }
+
+    /// Raises the gas pressure of the cell, such as when something explodes.
+    pub fn add_pressure(&mut self, x: usize, y: usize, amount: f32) {
+        self.set_pressure(x, y, self.pressure(x, y) + amount);
+    }
+
     pub fn swap(&mut self, x1: usize, y1: usize, x2: usize, y2: usize) {
         let index1 = self.to_index(x1, y1);
         let index2 = self.to_index(x2, y2);
@@ -121,6 +244,95 @@ impl Sandbox {
         &self.chunks
     }
 
+    /// Removes every chunk from the sandbox, leaving it empty until [`Sandbox::set_chunks`] is called.
+    pub fn take_chunks(&mut self) -> Vec<SandboxChunk> {
+        std::mem::take(&mut self.chunks)
+    }
+
+    /// Replaces every chunk in row-major order and wakes them so rendering and colliders rebuild.
+    pub fn set_chunks(&mut self, chunks: Vec<SandboxChunk>) {
+        assert_eq!(
+            chunks.len(),
+            self.x_chunks * self.y_chunks,
+            "Chunk count doesn't match the sandbox"
+        );
+        self.chunks = chunks;
+
+        for (i, chunk) in self.chunks.iter_mut().enumerate() {
+            chunk.local_position = (i % self.x_chunks, i / self.x_chunks);
+            chunk.strong_tick();
+        }
+    }
+
+    /// Returns the particles flying above the grid.
+    pub fn flying(&self) -> &[FlyingParticle] {
+        &self.flying
+    }
+
+    /// Adds a particle that was taken out of the grid to the flying ones.
+    pub(crate) fn launch(&mut self, flying: FlyingParticle) {
+        self.flying.push(flying);
+    }
+
+    pub(crate) fn take_flying(&mut self) -> Vec<FlyingParticle> {
+        std::mem::take(&mut self.flying)
+    }
+
+    pub(crate) fn set_flying(&mut self, flying: Vec<FlyingParticle>) {
+        self.flying = flying;
+    }
+
+    /// Returns the random number generator of the chunk at the position, or of the nearest chunk
+    /// when the position is outside of the sandbox.
+    pub fn rng(&mut self, x: usize, y: usize) -> &mut SandboxRng {
+        let (min_x, min_y) = self.min_position();
+        let x = x.clamp(min_x, min_x + self.total_width - 1);
+        let y = y.clamp(min_y, min_y + self.total_height - 1);
+        self.get_chunk_mut(x, y).rng()
+    }
+
+    /// Creates a particle of the material using the random number generator at the position.
+    ///
+    /// Returns `None` when the material was never registered or the position is outside of the
+    /// sandbox.
+    pub fn create_particle(
+        &mut self,
+        material: MaterialId,
+        x: usize,
+        y: usize,
+    ) -> Option<Particle> {
+        let index = self.to_index(x, y);
+        let chunk = self.chunks.get_mut(index)?;
+        self.materials.create(material, chunk.rng())
+    }
+
+    /// Returns the material of the particle at the position, or `None` when the cell is empty.
+    pub fn material_at(&self, x: usize, y: usize) -> Option<MaterialId> {
+        self.checked_get(x, y).map(|particle| particle.material)
+    }
+
+    /// Returns true if the cell at the position holds a particle of the material.
+    pub fn is_material_at(&self, x: usize, y: usize, material: MaterialId) -> bool {
+        self.material_at(x, y) == Some(material)
+    }
+
+    pub fn materials(&self) -> &MaterialRegistry {
+        &self.materials
+    }
+
+    pub fn set_materials(&mut self, materials: MaterialRegistry) {
+        self.materials = materials;
+    }
+
+    /// Temperature of the air that particles next to empty cells exchange heat with.
+    pub fn ambient(&self) -> Ambient {
+        self.ambient
+    }
+
+    pub fn set_ambient(&mut self, ambient: Ambient) {
+        self.ambient = ambient;
+    }
+
     fn strong_tick_neighbors(&mut self, x: usize, y: usize) {
         let search_directions = [
             (x.overflowing_sub(1).0, y),
@@ -134,12 +346,37 @@ impl Sandbox {
         ];
 
         for (neighbor_x, neighbor_y) in search_directions.into_iter() {
-            if self.out_of_bounds_usize(neighbor_x, neighbor_y) {
-                continue;
+            self.strong_tick(neighbor_x, neighbor_y);
+        }
+    }
+
+    /// Keeps an explosion that reaches outside of the view for later, returning whether it was
+    /// deferred. Explosions are never deferred outside of views.
+    pub(crate) fn defer_explosion(&mut self, x: usize, y: usize, radius: i32) -> bool {
+        let (x, y) = (x as i32, y as i32);
+        let fits = !self.out_of_bounds_i32(x - radius, y - radius)
+            && !self.out_of_bounds_i32(x + radius, y + radius);
+        match &mut self.deferred_ticks {
+            Some(deferred) if !fits => {
+                deferred.explosions.push((x as usize, y as usize, radius));
+                true
             }
+            _ => false,
+        }
+    }
 
-            self.get_chunk_mut(neighbor_x, neighbor_y).strong_tick();
+    /// Keeps the particle at the position simulated during the next tick.
+    pub fn weak_tick(&mut self, x: usize, y: usize) {
+        if self.out_of_bounds_usize(x, y) {
+            if let Some(deferred) = &mut self.deferred_ticks {
+                deferred.weak.push((x, y));
+            }
+            return;
         }
+
+        let (chunk_width, chunk_height) = (self.chunk_width, self.chunk_height);
+        self.get_chunk_mut(x, y)
+            .weak_tick_at(x % chunk_width, y % chunk_height);
     }
 
     pub fn mark_updated(&mut self, x: usize, y: usize) {
@@ -190,15 +427,69 @@ impl Sandbox {
         self.total_height
     }
 
+    pub fn x_chunks(&self) -> usize {
+        self.x_chunks
+    }
+
+    pub fn y_chunks(&self) -> usize {
+        self.y_chunks
+    }
+
+    pub fn chunk_width(&self) -> usize {
+        self.chunk_width
+    }
+
+    pub fn chunk_height(&self) -> usize {
+        self.chunk_height
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub(crate) fn set_ticks(&mut self, ticks: u64) {
+        self.ticks = ticks;
+    }
+
+    pub(crate) fn advance_ticks(&mut self) {
+        self.ticks += 1;
+    }
+
     pub fn out_of_bounds_i32(&self, x: i32, y: i32) -> bool {
-        x < 0 || x >= self.total_width as i32 || y < 0 || y >= self.total_height as i32
+        let (min_x, min_y) = self.min_position();
+        let (min_x, min_y) = (min_x as i32, min_y as i32);
+
+        x < min_x
+            || x >= min_x + self.total_width as i32
+            || y < min_y
+            || y >= min_y + self.total_height as i32
     }
 
     pub fn out_of_bounds_usize(&self, x: usize, y: usize) -> bool {
-        x >= self.total_width || y >= self.total_height
+        let (min_x, min_y) = self.min_position();
+
+        x < min_x || x >= min_x + self.total_width || y < min_y || y >= min_y + self.total_height
+    }
+
+    /// Returns the lowest position inside the sandbox, which is only non-zero for views.
+    fn min_position(&self) -> (usize, usize) {
+        (
+            self.chunk_offset.0 * self.chunk_width,
+            self.chunk_offset.1 * self.chunk_height,
+        )
     }
 
     fn to_index(&self, x: usize, y: usize) -> usize {
-        ((y / self.chunk_height) * self.x_chunks) + x / self.chunk_width
+        if self.out_of_bounds_usize(x, y) {
+            return usize::MAX;
+        }
+
+        ((y / self.chunk_height - self.chunk_offset.1) * self.x_chunks) + x / self.chunk_width
+            - self.chunk_offset.0
     }
 }
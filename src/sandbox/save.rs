@@ -0,0 +1,337 @@
+//! Saves a whole [`Sandbox`] to a single versioned binary file, and swaps a loaded one into the world.
+//!
+//! A save starts with [`SAVE_MAGIC`], the format version and the compression, followed by the
+//! bincode encoded sandbox. Particles keep their velocity, health and effect state, and the
+//! material names are stored along with them so a save still loads after materials were added.
+
+use std::{
+    borrow::Cow,
+    io::{self, ErrorKind, Read, Write},
+};
+
+use bevy::{ecs::world::Command, prelude::*, render::render_resource::Extent3d};
+use bincode::Options;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    collider::ColliderStorage,
+    flying::FlyingParticle,
+    history::EditHistory,
+    material::MaterialRegistry,
+    particle::{Ambient, Particle},
+    sandbox::Sandbox,
+};
+
+/// Bytes every save starts with.
+pub const SAVE_MAGIC: [u8; 4] = *b"FSSB";
+
+/// Version of the format written by [`Sandbox::save_to`].
+pub const SAVE_VERSION: u16 = 1;
+
+/// Most cells a loaded sandbox may have, so a corrupt or hostile save can't make loading
+/// allocate more memory than the machine has.
+const MAX_CELLS: usize = 1 << 24;
+
+/// Most bytes the body of a save may take up for every cell of its sandbox, which leaves room
+/// for a flying particle on top of the one in the cell. The encoded particles are never larger
+/// than twice their size in memory.
+const MAX_BYTES_PER_CELL: u64 = 4 * std::mem::size_of::<Option<Particle>>() as u64;
+
+/// Most bytes the material names of a save may take up.
+const MAX_MATERIAL_BYTES: u64 = 1 << 22;
+
+/// How the body of a save is compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SaveCompression {
+    None,
+    /// Zlib compression, which shrinks mostly empty sandboxes a lot.
+    #[default]
+    Zlib,
+}
+
+impl SaveCompression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zlib => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::None),
+            1 => Some(Self::Zlib),
+            _ => None,
+        }
+    }
+}
+
+/// Dimensions of the saved sandbox, which are read and checked on their own before the rest of
+/// the save.
+#[derive(Serialize, Deserialize)]
+struct SaveDimensions {
+    x_chunks: usize,
+    y_chunks: usize,
+    chunk_width: usize,
+    chunk_height: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SandboxSave<'a> {
+    seed: u64,
+    ticks: u64,
+    ambient: Ambient,
+    /// Material names by id when the sandbox was saved.
+    materials: Vec<Cow<'a, str>>,
+    /// Particles of every chunk in row-major order.
+    chunks: Vec<Cow<'a, [Option<Particle>]>>,
+    flying: Cow<'a, [FlyingParticle]>,
+}
+
+impl Sandbox {
+    /// Writes every particle of the sandbox, including the flying ones, along with its
+    /// dimensions, seed, tick count and ambient temperature.
+    pub fn save_to(&self, mut writer: impl Write, compression: SaveCompression) -> io::Result<()> {
+        let dimensions = SaveDimensions {
+            x_chunks: self.x_chunks(),
+            y_chunks: self.y_chunks(),
+            chunk_width: self.chunk_width(),
+            chunk_height: self.chunk_height(),
+        };
+        let save = SandboxSave {
+            seed: self.seed(),
+            ticks: self.ticks(),
+            ambient: self.ambient(),
+            materials: self.materials().names().map(Cow::Borrowed).collect(),
+            chunks: self
+                .get_all_chunks()
+                .iter()
+                .map(|chunk| Cow::Borrowed(chunk.particles()))
+                .collect(),
+            flying: Cow::Borrowed(self.flying()),
+        };
+
+        writer.write_all(&SAVE_MAGIC)?;
+        writer.write_all(&SAVE_VERSION.to_le_bytes())?;
+        writer.write_all(&[compression.to_byte()])?;
+
+        match compression {
+            SaveCompression::None => {
+                bincode::serialize_into(&mut writer, &dimensions).map_err(invalid_data)?;
+                bincode::serialize_into(&mut writer, &save).map_err(invalid_data)?;
+            }
+            SaveCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(&mut writer, flate2::Compression::default());
+                bincode::serialize_into(&mut encoder, &dimensions).map_err(invalid_data)?;
+                bincode::serialize_into(&mut encoder, &save).map_err(invalid_data)?;
+                encoder.finish()?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Reads a sandbox written by [`Sandbox::save_to`], using `materials` for its particles.
+    ///
+    /// Material ids are matched up by name, so the save fails to load when one of its
+    /// particles uses a material that isn't registered. The random number generators start
+    /// over from the seed, so loading the same save always continues the same way.
+    ///
+    /// The dimensions are checked before the particles are read, and the particles can't take
+    /// up more bytes than the dimensions leave room for, so even a corrupt or hostile save
+    /// can't make loading run out of memory.
+    pub fn load_from(mut reader: impl Read, materials: &MaterialRegistry) -> io::Result<Sandbox> {
+        let mut header = [0; 7];
+        reader.read_exact(&mut header)?;
+        if header[..4] != SAVE_MAGIC {
+            return Err(invalid_data("not a sandbox save"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != SAVE_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported save version {}, expected {}",
+                version, SAVE_VERSION
+            )));
+        }
+        let compression = SaveCompression::from_byte(header[6])
+            .ok_or_else(|| invalid_data(format!("unknown save compression {}", header[6])))?;
+
+        let mut reader: Box<dyn Read> = match compression {
+            SaveCompression::None => Box::new(reader),
+            SaveCompression::Zlib => Box::new(ZlibDecoder::new(reader)),
+        };
+        // The same encoding `bincode::serialize_into` writes, with a limit on the bytes read
+        let options = |limit: u64| {
+            bincode::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes()
+                .with_limit(limit)
+        };
+
+        // Four `usize`s, which bincode always writes as 8 bytes
+        let dimensions: SaveDimensions = options(4 * 8)
+            .deserialize_from(&mut reader)
+            .map_err(invalid_data)?;
+        let SaveDimensions {
+            x_chunks,
+            y_chunks,
+            chunk_width,
+            chunk_height,
+        } = dimensions;
+
+        if x_chunks == 0 || y_chunks == 0 || chunk_width == 0 || chunk_height == 0 {
+            return Err(invalid_data("save has an empty sandbox"));
+        }
+
+        let too_large = || {
+            invalid_data(format!(
+                "save has {}x{} chunks of {}x{} cells, more than the {} cells a sandbox can have",
+                x_chunks, y_chunks, chunk_width, chunk_height, MAX_CELLS
+            ))
+        };
+        let chunk_cells = chunk_width
+            .checked_mul(chunk_height)
+            .ok_or_else(too_large)?;
+        let chunk_count = x_chunks.checked_mul(y_chunks).ok_or_else(too_large)?;
+        let cells = match chunk_cells.checked_mul(chunk_count) {
+            Some(cells) if cells <= MAX_CELLS => cells,
+            _ => return Err(too_large()),
+        };
+
+        let save: SandboxSave = options(cells as u64 * MAX_BYTES_PER_CELL + MAX_MATERIAL_BYTES)
+            .deserialize_from(reader)
+            .map_err(invalid_data)?;
+
+        if save.chunks.len() != chunk_count {
+            return Err(invalid_data(format!(
+                "save has {} chunks, expected {}",
+                save.chunks.len(),
+                chunk_count
+            )));
+        }
+        if save
+            .chunks
+            .iter()
+            .any(|particles| particles.len() != chunk_cells)
+        {
+            return Err(invalid_data("save has a chunk of the wrong size"));
+        }
+
+        let ids = save
+            .materials
+            .iter()
+            .map(|name| materials.id(name))
+            .collect::<Vec<_>>();
+
+        let mut sandbox = Sandbox::new(x_chunks, y_chunks, chunk_width, chunk_height, save.seed);
+        sandbox.set_ticks(save.ticks);
+        sandbox.set_ambient(save.ambient);
+        sandbox.set_materials(materials.clone());
+
+        let mut missing = None;
+        let mut remap = |particle: &mut Particle| {
+            particle.map_materials(|id| match ids.get(id.index()).copied().flatten() {
+                Some(id) => id,
+                None => {
+                    missing = Some(id);
+                    id
+                }
+            });
+        };
+
+        let mut chunks = sandbox.take_chunks();
+        for (chunk, particles) in chunks.iter_mut().zip(save.chunks) {
+            let mut particles = particles.into_owned();
+            particles.iter_mut().flatten().for_each(&mut remap);
+            chunk.load_particles(particles);
+        }
+
+        let mut flying = save.flying.into_owned();
+        for flying in flying.iter_mut() {
+            remap(&mut flying.particle);
+        }
+
+        if let Some(id) = missing {
+            let name = save.materials.get(id.index()).map_or("?", |name| name);
+            return Err(invalid_data(format!(
+                "save uses the material {} which isn't registered",
+                name
+            )));
+        }
+
+        sandbox.set_chunks(chunks);
+        sandbox.set_flying(flying);
+
+        Ok(sandbox)
+    }
+}
+
+pub(crate) fn invalid_data(
+    error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error)
+}
+
+/// Replaces the [`Sandbox`] of an entity, usually with one from [`Sandbox::load_from`].
+///
+/// Every chunk is woken so the image and colliders are rebuilt, and both are resized when
+/// the new sandbox has different dimensions. Its edits are dropped from the [`EditHistory`].
+pub struct ReplaceSandbox {
+    pub entity: Entity,
+    pub sandbox: Sandbox,
+}
+
+impl Command for ReplaceSandbox {
+    fn apply(self, world: &mut World) {
+        let mut sandbox = self.sandbox;
+        if let Some(materials) = world.get_resource::<MaterialRegistry>() {
+            sandbox.set_materials(materials.clone());
+        }
+        let chunks = sandbox.take_chunks();
+        sandbox.set_chunks(chunks);
+
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            warn!(
+                "Can't replace the sandbox of missing entity {:?}",
+                self.entity
+            );
+            return;
+        };
+
+        let size = Extent3d {
+            width: sandbox.width() as u32,
+            height: sandbox.height() as u32,
+            depth_or_array_layers: 1,
+        };
+        let chunk_count = sandbox.x_chunks() * sandbox.y_chunks();
+        let image = entity.get::<Handle<Image>>().cloned();
+        let old_colliders = entity
+            .get_mut::<ColliderStorage>()
+            .map(|mut storage| std::mem::replace(&mut *storage, ColliderStorage::new(chunk_count)));
+        entity.insert(sandbox);
+
+        for collider in old_colliders
+            .into_iter()
+            .flat_map(|storage| storage.colliders)
+            .flatten()
+            .flatten()
+        {
+            world.despawn(collider);
+        }
+
+        // Edits of the old contents can't be undone on the new ones
+        if let Some(mut history) = world.get_resource_mut::<EditHistory>() {
+            history.forget(self.entity);
+        }
+
+        if let (Some(image), Some(mut images)) = (image, world.get_resource_mut::<Assets<Image>>())
+        {
+            if let Some(image) = images.get_mut(&image) {
+                if image.texture_descriptor.size != size {
+                    image.resize(size);
+                }
+            }
+        }
+    }
+}
@@ -1,31 +1,266 @@
-use bevy::prelude::*;
+use std::cmp::Reverse;
 
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::chunk::{DirtyRect, SandboxChunk};
 use super::effects::acidity::tick_acidity;
 use super::effects::growable::tick_growable;
 use super::effects::movement::tick_movement;
-use super::effects::temperature::tick_temperature;
+use super::effects::reaction::tick_reactions;
+use super::effects::temperature::{explode, tick_temperature};
 use super::effects::tick_life::tick_life;
+use super::effects::wetness::tick_wetness;
+use super::flying::update_flying;
+use super::flying::FlyingParticle;
+use super::pressure::update_pressure;
 use super::sandbox::*;
+use super::SandboxConfig;
+
+/// Chunks updated by a single task are grouped into tiles of this many chunks along each axis.
+const TILE_SIZE: usize = 2;
+
+/// The four checkerboard phases, so tiles updated at the same time are never next to each other.
+const PHASES: [(usize, usize); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+/// How the chunks of a sandbox are scheduled during a tick.
+///
+/// Both modes update the same tiles in the same order, so a fixed seed gives identical results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkUpdateMode {
+    /// Updates every tile on the current thread.
+    #[default]
+    Serial,
+    /// Updates the tiles of each checkerboard phase in parallel on the [`ComputeTaskPool`].
+    Parallel,
+}
+
+/// Order the particles of a tile are updated in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SweepStrategy {
+    /// Walks each chunk column by column from left to right, bottom to top.
+    #[default]
+    ColumnMajor,
+    /// Walks the rows from bottom to top, each from left to right.
+    RowMajor,
+    /// Walks the rows from bottom to top, flipping the horizontal direction every tick.
+    Alternating,
+    /// Walks the particles of each chunk in a random order.
+    Randomized,
+}
 
-pub fn update_particles(mut sandbox_query: Query<&mut Sandbox>) {
-    let mut sandbox = sandbox_query
-        .get_single_mut()
-        .expect("There should be a Sandbox at this point");
+pub fn update_particles(mut sandbox_query: Query<&mut Sandbox>, config: Res<SandboxConfig>) {
+    for mut sandbox in &mut sandbox_query {
+        step_sandbox(&mut sandbox, &config);
+    }
+}
 
+/// Advances every awake chunk of the sandbox by a single tick.
+///
+/// Chunks are grouped into tiles that are updated in four checkerboard phases. Each tile
+/// is simulated in a view that also holds the chunks bordering it, so particles can move up
+/// to a chunk past their tile while tiles of the same phase never share a chunk. Particles
+/// move at most a chunk per tick, and explosions reaching further go off after the phase.
+/// Tiles whose chunks are all asleep are skipped.
+pub fn step_sandbox(sandbox: &mut Sandbox, config: &SandboxConfig) {
     sandbox.reset_ticked_chunks();
 
-    for x in 0..sandbox.width() {
-        for y in 0..sandbox.height() {
-            let current_chunk = sandbox.get_chunk(x, y);
-            if !current_chunk.is_weak_ticked() && !current_chunk.is_strong_ticked() {
-                continue;
+    // Only the cells marked during the last tick can do anything
+    let dirty_rects = sandbox
+        .get_all_chunks()
+        .iter()
+        .map(|chunk| chunk.dirty_rect())
+        .collect::<Vec<_>>();
+
+    let (x_chunks, y_chunks) = (sandbox.x_chunks(), sandbox.y_chunks());
+    for phase in PHASES {
+        let mut chunks = sandbox
+            .take_chunks()
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        let mut tiles = vec![];
+        for tile_x in (phase.0..x_chunks.div_ceil(TILE_SIZE)).step_by(2) {
+            for tile_y in (phase.1..y_chunks.div_ceil(TILE_SIZE)).step_by(2) {
+                tiles.extend(Tile::take(
+                    sandbox,
+                    &mut chunks,
+                    &dirty_rects,
+                    tile_x,
+                    tile_y,
+                ));
+            }
+        }
+
+        match config.update_mode {
+            ChunkUpdateMode::Serial => {
+                for tile in tiles.iter_mut() {
+                    tile.step(&dirty_rects, x_chunks, config.sweep);
+                }
             }
+            ChunkUpdateMode::Parallel => {
+                let dirty_rects = &dirty_rects;
+                ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+                    for tile in tiles.iter_mut() {
+                        scope.spawn(async move { tile.step(dirty_rects, x_chunks, config.sweep) });
+                    }
+                });
+            }
+        }
 
-            step_particle(x, y, &mut sandbox);
+        let mut deferred = vec![];
+        for tile in tiles {
+            let (ticks, launched) = tile.restore(&mut chunks, x_chunks);
+            deferred.push(ticks);
+            for flying in launched {
+                sandbox.launch(flying);
+            }
+        }
+
+        sandbox.restore_chunks(chunks.into_iter().map(Option::unwrap).collect());
+
+        // Ticks that crossed a view's edge are applied in tile order to stay deterministic
+        for ticks in deferred {
+            for (x, y) in ticks.strong {
+                sandbox.strong_tick(x, y);
+            }
+            for (x, y) in ticks.weak {
+                sandbox.weak_tick(x, y);
+            }
+            for (x, y, radius) in ticks.explosions {
+                // Explosives an earlier explosion already turned into sparks don't go off
+                let explosive = sandbox
+                    .get(x, y)
+                    .and_then(|particle| particle.temperature)
+                    .is_some_and(|temperature| temperature.explosion_radius == radius);
+                if explosive {
+                    explode(x, y, radius, sandbox);
+                }
+            }
         }
     }
 
+    update_flying(sandbox);
+    update_pressure(sandbox);
+
     sandbox.reset_updated();
+    sandbox.advance_ticks();
+}
+
+/// Chunks updated by one task, along with the chunks bordering them.
+struct Tile {
+    /// Chunks whose particles are stepped, as `(min, max)` exclusive chunk positions.
+    updated: ((usize, usize), (usize, usize)),
+    /// Chunks the particles can reach, as `(min, max)` exclusive chunk positions.
+    reachable: ((usize, usize), (usize, usize)),
+    view: Sandbox,
+}
+
+impl Tile {
+    /// Takes the chunks of the tile and the ones bordering it out of `chunks`, or returns `None`
+    /// when none of the tile's chunks need to be simulated.
+    fn take(
+        sandbox: &Sandbox,
+        chunks: &mut [Option<SandboxChunk>],
+        dirty_rects: &[Option<DirtyRect>],
+        tile_x: usize,
+        tile_y: usize,
+    ) -> Option<Self> {
+        let (x_chunks, y_chunks) = (sandbox.x_chunks(), sandbox.y_chunks());
+        let min = (tile_x * TILE_SIZE, tile_y * TILE_SIZE);
+        let max = (
+            (min.0 + TILE_SIZE).min(x_chunks),
+            (min.1 + TILE_SIZE).min(y_chunks),
+        );
+        let awake = (min.1..max.1).any(|chunk_y| {
+            (min.0..max.0).any(|chunk_x| dirty_rects[chunk_y * x_chunks + chunk_x].is_some())
+        });
+        if !awake {
+            return None;
+        }
+        let reachable_min = (min.0.saturating_sub(1), min.1.saturating_sub(1));
+        let reachable_max = ((max.0 + 1).min(x_chunks), (max.1 + 1).min(y_chunks));
+
+        let mut view_chunks = vec![];
+        for chunk_y in reachable_min.1..reachable_max.1 {
+            for chunk_x in reachable_min.0..reachable_max.0 {
+                let chunk = chunks[chunk_y * x_chunks + chunk_x]
+                    .take()
+                    .expect("Tiles of the same phase shouldn't share chunks");
+                view_chunks.push(chunk);
+            }
+        }
+
+        Some(Self {
+            updated: (min, max),
+            reachable: (reachable_min, reachable_max),
+            view: sandbox.view(
+                reachable_min,
+                reachable_max.0 - reachable_min.0,
+                reachable_max.1 - reachable_min.1,
+                view_chunks,
+            ),
+        })
+    }
+
+    fn step(&mut self, dirty_rects: &[Option<DirtyRect>], x_chunks: usize, sweep: SweepStrategy) {
+        let ((min_x, min_y), (max_x, max_y)) = self.updated;
+        let (chunk_width, chunk_height) = (self.view.chunk_width(), self.view.chunk_height());
+
+        let mut positions = vec![];
+        for chunk_x in min_x..max_x {
+            for chunk_y in min_y..max_y {
+                let Some(rect) = dirty_rects[chunk_y * x_chunks + chunk_x] else {
+                    continue;
+                };
+
+                let (offset_x, offset_y) = (chunk_x * chunk_width, chunk_y * chunk_height);
+                let start = positions.len();
+                positions.extend(rect.iter().map(|(x, y)| (offset_x + x, offset_y + y)));
+
+                if sweep == SweepStrategy::Randomized {
+                    positions[start..].shuffle(self.view.rng(offset_x, offset_y));
+                }
+            }
+        }
+
+        // Rows span the whole tile so particles don't favor the side of their chunk
+        match sweep {
+            SweepStrategy::ColumnMajor | SweepStrategy::Randomized => {}
+            SweepStrategy::RowMajor => positions.sort_unstable_by_key(|&(x, y)| (y, x)),
+            SweepStrategy::Alternating if self.view.ticks().is_multiple_of(2) => {
+                positions.sort_unstable_by_key(|&(x, y)| (y, x))
+            }
+            SweepStrategy::Alternating => positions.sort_unstable_by_key(|&(x, y)| (y, Reverse(x))),
+        }
+
+        for (x, y) in positions {
+            step_particle(x, y, &mut self.view);
+        }
+    }
+
+    fn restore(
+        self,
+        chunks: &mut [Option<SandboxChunk>],
+        x_chunks: usize,
+    ) -> (DeferredTicks, Vec<FlyingParticle>) {
+        let ((min_x, min_y), (max_x, max_y)) = self.reachable;
+        let (view_chunks, deferred, launched) = self.view.into_view_parts();
+
+        let mut view_chunks = view_chunks.into_iter();
+        for chunk_y in min_y..max_y {
+            for chunk_x in min_x..max_x {
+                chunks[chunk_y * x_chunks + chunk_x] = view_chunks.next();
+            }
+        }
+
+        (deferred, launched)
+    }
 }
 
 fn step_particle(x: usize, y: usize, sandbox: &mut Sandbox) {
@@ -42,9 +277,15 @@ fn step_particle(x: usize, y: usize, sandbox: &mut Sandbox) {
         None => return,
     }
 
+    if tick_reactions(x, y, sandbox) {
+        return;
+    }
     if tick_acidity(x, y, sandbox) {
         return;
     }
+    if tick_wetness(x, y, sandbox) {
+        return;
+    }
     if tick_temperature(x, y, sandbox) {
         return;
     }
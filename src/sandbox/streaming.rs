@@ -0,0 +1,272 @@
+//! Streams chunks in and out of a [`Sandbox`] so it can act as a window into an unbounded world.
+//!
+//! The sandbox keeps its fixed size and is re-centered on the [`SandboxFocus`] entity.
+//! Chunks that leave the window are written to region files, and chunks that enter it are
+//! loaded from those files or created with the [`ChunkGenerator`].
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    chunk::SandboxChunk, flying::land_all, material::MaterialRegistry, particle::Particle,
+    rng::chunk_rng_at, sandbox::Sandbox, simulation::update_particles,
+};
+
+/// Fills a freshly created chunk with the given world key.
+pub type ChunkGenerator = fn(IVec2, &mut SandboxChunk, &MaterialRegistry);
+
+pub struct SandboxStreamingPlugin;
+
+impl Plugin for SandboxStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, stream_chunks.before(update_particles))
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+/// Marks the entity the streamed sandboxes are centered around, usually the camera or player.
+#[derive(Component)]
+pub struct SandboxFocus;
+
+/// Turns the [`Sandbox`] on the same entity into a window that follows the [`SandboxFocus`].
+#[derive(Component)]
+pub struct StreamedSandbox {
+    /// World key of the chunk at the bottom left of the window.
+    pub origin: IVec2,
+    /// Directory the region files are stored in.
+    pub region_dir: PathBuf,
+    /// Number of chunks along each side of a region file.
+    pub region_size: i32,
+    /// Creates chunks that have never been saved.
+    pub generator: ChunkGenerator,
+    loaded: bool,
+}
+
+impl StreamedSandbox {
+    pub fn new(region_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            origin: IVec2::ZERO,
+            region_dir: region_dir.into(),
+            region_size: 16,
+            generator: |_, _, _| {},
+            loaded: false,
+        }
+    }
+
+    pub fn with_generator(mut self, generator: ChunkGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Returns the world key of the chunk at the local chunk position.
+    pub fn chunk_key(&self, local_position: (usize, usize)) -> IVec2 {
+        self.origin + IVec2::new(local_position.0 as i32, local_position.1 as i32)
+    }
+
+    /// Moves the window so its bottom left chunk is `origin`, saving the chunks that leave it.
+    pub fn shift(&mut self, sandbox: &mut Sandbox, origin: IVec2) -> io::Result<()> {
+        let size = IVec2::new(sandbox.x_chunks() as i32, sandbox.y_chunks() as i32);
+        let in_window = |key: IVec2| key.cmpge(origin).all() && key.cmplt(origin + size).all();
+
+        // Flying particles are only kept track of inside the window
+        land_all(sandbox);
+
+        let mut kept = HashMap::new();
+        let mut unloaded = vec![];
+        let old_chunks = sandbox.take_chunks();
+        if self.loaded {
+            for chunk in old_chunks {
+                let key = self.chunk_key(chunk.local_position);
+                if in_window(key) {
+                    kept.insert(key, chunk);
+                } else {
+                    unloaded.push((key, chunk));
+                }
+            }
+        }
+
+        let saved = self.save_chunks(unloaded.iter().map(|(key, chunk)| (*key, chunk)));
+
+        let mut regions = HashMap::new();
+        let mut chunks = Vec::with_capacity((size.x * size.y) as usize);
+        for i in 0..size.x * size.y {
+            let key = origin + IVec2::new(i % size.x, i / size.x);
+            let chunk = match kept.remove(&key) {
+                Some(chunk) => chunk,
+                None => self.load_or_generate(key, sandbox, &mut regions),
+            };
+            chunks.push(chunk);
+        }
+
+        sandbox.set_chunks(chunks);
+        self.origin = origin;
+        self.loaded = true;
+
+        saved
+    }
+
+    /// Writes every loaded chunk to its region file.
+    pub fn save_all(&self, sandbox: &Sandbox) -> io::Result<()> {
+        if !self.loaded {
+            return Ok(());
+        }
+
+        self.save_chunks(
+            sandbox
+                .get_all_chunks()
+                .iter()
+                .map(|chunk| (self.chunk_key(chunk.local_position), chunk)),
+        )
+    }
+
+    fn save_chunks<'a>(
+        &self,
+        chunks: impl Iterator<Item = (IVec2, &'a SandboxChunk)>,
+    ) -> io::Result<()> {
+        let mut by_region: HashMap<IVec2, Vec<(IVec2, &SandboxChunk)>> = HashMap::new();
+        for (key, chunk) in chunks {
+            by_region
+                .entry(self.region_key(key))
+                .or_default()
+                .push((key, chunk));
+        }
+
+        for (region_key, entries) in by_region {
+            let mut region = self.read_region(region_key)?;
+            for (key, chunk) in entries {
+                region
+                    .chunks
+                    .insert((key.x, key.y), chunk.particles().to_vec());
+            }
+            self.write_region(region_key, &region)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_or_generate(
+        &self,
+        key: IVec2,
+        sandbox: &Sandbox,
+        regions: &mut HashMap<IVec2, RegionFile>,
+    ) -> SandboxChunk {
+        let mut chunk = SandboxChunk::new(
+            sandbox.chunk_width(),
+            sandbox.chunk_height(),
+            (0, 0),
+            chunk_rng_at(sandbox.seed(), key),
+        );
+
+        let region_key = self.region_key(key);
+        let region = regions.entry(region_key).or_insert_with(|| {
+            self.read_region(region_key).unwrap_or_else(|error| {
+                error!("Failed to read region {}: {}", region_key, error);
+                RegionFile::default()
+            })
+        });
+
+        match region.chunks.get(&(key.x, key.y)) {
+            Some(particles) if particles.len() == chunk.width() * chunk.height() => {
+                chunk.load_particles(particles.clone())
+            }
+            Some(_) => {
+                error!("Chunk {} has a different size than the sandbox", key);
+                (self.generator)(key, &mut chunk, sandbox.materials());
+            }
+            None => (self.generator)(key, &mut chunk, sandbox.materials()),
+        }
+
+        chunk
+    }
+
+    fn region_key(&self, key: IVec2) -> IVec2 {
+        IVec2::new(
+            key.x.div_euclid(self.region_size),
+            key.y.div_euclid(self.region_size),
+        )
+    }
+
+    fn region_path(&self, region_key: IVec2) -> PathBuf {
+        self.region_dir
+            .join(format!("r.{}.{}.bin", region_key.x, region_key.y))
+    }
+
+    fn read_region(&self, region_key: IVec2) -> io::Result<RegionFile> {
+        let bytes = match fs::read(self.region_path(region_key)) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(RegionFile::default()),
+            Err(error) => return Err(error),
+        };
+
+        bincode::deserialize(&bytes).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+    }
+
+    fn write_region(&self, region_key: IVec2, region: &RegionFile) -> io::Result<()> {
+        let bytes = bincode::serialize(region)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+        fs::create_dir_all(&self.region_dir)?;
+        fs::write(self.region_path(region_key), bytes)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct RegionFile {
+    chunks: HashMap<(i32, i32), Vec<Option<Particle>>>,
+}
+
+pub fn stream_chunks(
+    focus_query: Query<&GlobalTransform, With<SandboxFocus>>,
+    mut sandbox_query: Query<(&mut Sandbox, &mut StreamedSandbox, &mut Transform)>,
+) {
+    let Ok(focus) = focus_query.get_single() else {
+        return;
+    };
+
+    for (mut sandbox, mut streamed, mut transform) in &mut sandbox_query {
+        let chunk_size = Vec2::new(sandbox.chunk_width() as f32, sandbox.chunk_height() as f32)
+            * transform.scale.truncate();
+        let focus_key = (focus.translation().truncate() / chunk_size)
+            .floor()
+            .as_ivec2();
+        let origin =
+            focus_key - IVec2::new(sandbox.x_chunks() as i32 / 2, sandbox.y_chunks() as i32 / 2);
+
+        if streamed.loaded && streamed.origin == origin {
+            continue;
+        }
+
+        if let Err(error) = streamed.shift(&mut sandbox, origin) {
+            error!("Failed to save chunks: {}", error);
+        }
+
+        // Keeps every cell at the same world position no matter where the window is
+        let window_center = origin.as_vec2() * chunk_size
+            + Vec2::new(sandbox.width() as f32, sandbox.height() as f32)
+                * transform.scale.truncate()
+                / 2.0;
+        transform.translation = window_center.extend(transform.translation.z);
+    }
+}
+
+fn save_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    sandbox_query: Query<(&Sandbox, &StreamedSandbox)>,
+) {
+    if exit_events.read().last().is_none() {
+        return;
+    }
+
+    for (sandbox, streamed) in &sandbox_query {
+        if let Err(error) = streamed.save_all(sandbox) {
+            error!("Failed to save chunks: {}", error);
+        }
+    }
+}
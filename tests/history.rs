@@ -0,0 +1,102 @@
+//! Records edits to a sandbox in an [`EditHistory`], and undoes and redoes them.
+
+use bevy::prelude::Entity;
+use bevy_falling_sand::sandbox::{
+    history::{CellEdit, Edit, EditHistory},
+    material::MaterialId,
+    sandbox::Sandbox,
+};
+
+#[test]
+fn undo_and_redo_restore_the_cells() {
+    let entity = Entity::from_raw(0);
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    let mut history = EditHistory::default();
+
+    // A stroke placing sand and then overwriting it with water
+    place(&mut sandbox, &mut history, entity, MaterialId::SAND, 1, 1);
+    place(&mut sandbox, &mut history, entity, MaterialId::WATER, 1, 1);
+    place(&mut sandbox, &mut history, entity, MaterialId::WATER, 2, 1);
+    history.finish();
+
+    history.undo().unwrap().clone().revert(&mut sandbox);
+    assert_eq!(sandbox.material_at(1, 1), None);
+    assert_eq!(sandbox.material_at(2, 1), None);
+    assert!(!history.can_undo());
+
+    history.redo().unwrap().clone().apply(&mut sandbox);
+    assert_eq!(sandbox.material_at(1, 1), Some(MaterialId::WATER));
+    assert_eq!(sandbox.material_at(2, 1), Some(MaterialId::WATER));
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn new_edit_clears_the_redo_stack() {
+    let entity = Entity::from_raw(0);
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    let mut history = EditHistory::default();
+
+    place(&mut sandbox, &mut history, entity, MaterialId::SAND, 1, 1);
+    history.finish();
+    history.undo().unwrap().clone().revert(&mut sandbox);
+    assert!(history.can_redo());
+
+    place(&mut sandbox, &mut history, entity, MaterialId::STONE, 3, 3);
+    history.finish();
+    assert!(!history.can_redo());
+    assert!(history.redo().is_none());
+}
+
+#[test]
+fn oldest_edits_are_dropped_past_the_capacity() {
+    let entity = Entity::from_raw(0);
+    let mut history = EditHistory::new(3);
+
+    for x in 0..5 {
+        history.push(Edit {
+            entity,
+            cells: vec![CellEdit {
+                x,
+                y: 0,
+                before: None,
+                after: None,
+            }],
+        });
+    }
+
+    // Only the last three edits are left, newest first
+    for x in (2..5).rev() {
+        assert_eq!(history.undo().map(|edit| edit.cells[0].x), Some(x));
+    }
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn forgetting_a_sandbox_keeps_the_edits_of_others() {
+    let (first, second) = (Entity::from_raw(0), Entity::from_raw(1));
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    let mut history = EditHistory::default();
+
+    place(&mut sandbox, &mut history, first, MaterialId::SAND, 1, 1);
+    place(&mut sandbox, &mut history, second, MaterialId::SAND, 2, 2);
+    place(&mut sandbox, &mut history, first, MaterialId::SAND, 3, 3);
+    history.finish();
+
+    history.forget(first);
+    assert_eq!(history.undo().map(|edit| edit.entity), Some(second));
+    assert!(history.undo().is_none());
+}
+
+fn place(
+    sandbox: &mut Sandbox,
+    history: &mut EditHistory,
+    entity: Entity,
+    material: MaterialId,
+    x: usize,
+    y: usize,
+) {
+    let before = sandbox.get(x, y).copied();
+    let particle = sandbox.create_particle(material, x, y);
+    sandbox.set(x, y, particle);
+    history.record(entity, x, y, before, particle);
+}
@@ -0,0 +1,119 @@
+//! Exports sandboxes to PNG images and imports them again through a [`Palette`].
+
+use bevy_falling_sand::sandbox::{
+    material::{MaterialId, MaterialRegistry},
+    palette::{Palette, PngExport},
+    sandbox::Sandbox,
+};
+
+const MATERIALS: [MaterialId; 4] = [
+    MaterialId::SAND,
+    MaterialId::WATER,
+    MaterialId::STONE,
+    MaterialId::LAVA,
+];
+
+#[test]
+fn colors_round_trip() {
+    let sandbox = scene();
+    let mut bytes = vec![];
+    sandbox.export_png(&mut bytes, PngExport::Colors).unwrap();
+
+    let mut imported = Sandbox::new(2, 2, 8, 8, 0);
+    let palette = Palette::new(&MaterialRegistry::default());
+    imported.import_png(&bytes[..], &palette).unwrap();
+
+    assert_same_materials(&sandbox, &imported);
+}
+
+#[test]
+fn material_indices_round_trip() {
+    let sandbox = scene();
+    let mut bytes = vec![];
+    sandbox
+        .export_png(&mut bytes, PngExport::MaterialIndices)
+        .unwrap();
+
+    let mut imported = Sandbox::new(2, 2, 8, 8, 0);
+    let palette = Palette::material_indices(&MaterialRegistry::default());
+    imported.import_png(&bytes[..], &palette).unwrap();
+
+    assert_same_materials(&sandbox, &imported);
+}
+
+#[test]
+fn unknown_colors_use_the_nearest_material() {
+    let materials = MaterialRegistry::default();
+    let color = |material| {
+        let (r, g, b, _) = materials.prototype(material).unwrap().color;
+        [r, g, b]
+    };
+    let [r, g, b] = color(MaterialId::SAND);
+    let off_sand = [r - 3, g + 2, b + 1];
+    let pixels = [
+        [color(MaterialId::WATER), off_sand],
+        [color(MaterialId::STONE), color(MaterialId::STONE)],
+    ];
+
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut png = encoder.write_header().unwrap();
+    png.write_image_data(&pixels.concat().concat()).unwrap();
+    png.finish().unwrap();
+
+    let mut sandbox = Sandbox::new(1, 1, 2, 2, 0);
+    sandbox
+        .import_png(&bytes[..], &Palette::new(&materials))
+        .unwrap();
+
+    // The top row of the image is the top of the sandbox
+    assert_eq!(sandbox.material_at(0, 1), Some(MaterialId::WATER));
+    assert_eq!(sandbox.material_at(1, 1), Some(MaterialId::SAND));
+    assert_eq!(sandbox.material_at(0, 0), Some(MaterialId::STONE));
+    assert_eq!(sandbox.material_at(1, 0), Some(MaterialId::STONE));
+}
+
+#[test]
+fn images_of_another_size_are_rejected() {
+    let mut bytes = vec![];
+    scene().export_png(&mut bytes, PngExport::Colors).unwrap();
+
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    let palette = Palette::new(&MaterialRegistry::default());
+    let error = sandbox.import_png(&bytes[..], &palette).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+/// Stripes of materials with empty cells between them.
+fn scene() -> Sandbox {
+    let mut sandbox = Sandbox::new(2, 2, 8, 8, 0);
+    for x in 0..sandbox.width() {
+        for y in 0..sandbox.height() {
+            if (x + y) % 5 == 0 {
+                continue;
+            }
+
+            let material = MATERIALS[(x + 2 * y) % MATERIALS.len()];
+            let particle = sandbox.create_particle(material, x, y);
+            sandbox.set(x, y, particle);
+        }
+    }
+
+    sandbox
+}
+
+fn assert_same_materials(a: &Sandbox, b: &Sandbox) {
+    for x in 0..a.width() {
+        for y in 0..a.height() {
+            assert_eq!(
+                a.material_at(x, y),
+                b.material_at(x, y),
+                "cell ({}, {}) differs",
+                x,
+                y
+            );
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Registers materials that react with water and checks what the water turns into.
+
+use bevy_falling_sand::{
+    sandbox::{
+        material::{MaterialId, MaterialRegistry, Product},
+        particle::MovementType,
+        particle_definition::{ParticleDefinition, ReactionDefinition},
+        sandbox::Sandbox,
+        simulation::step_sandbox,
+    },
+    SandboxConfig,
+};
+
+#[test]
+fn registered_reaction_fires() {
+    let mut materials = MaterialRegistry::default();
+    let stone = materials.id("Stone").unwrap();
+    let reagent = materials.register(reagent("Stone"));
+    assert_eq!(materials.validate(), Ok(()));
+
+    let sandbox = react(materials, reagent);
+
+    assert_eq!(sandbox.material_at(1, 0), Some(reagent));
+    assert_eq!(sandbox.material_at(2, 0), Some(stone));
+}
+
+#[test]
+fn reaction_producing_an_unregistered_material_is_rejected() {
+    let mut materials = MaterialRegistry::default();
+    let reagent = materials.register(reagent("Unobtainium"));
+    assert!(materials.validate().is_err());
+
+    // The water is left alone instead of disappearing
+    let sandbox = react(materials.clone(), reagent);
+    assert_eq!(sandbox.material_at(2, 0), Some(MaterialId::WATER));
+
+    // Until the product is registered
+    let unobtainium = materials.register(ParticleDefinition {
+        name: "Unobtainium".into(),
+        movement_type: MovementType::Solid,
+        ..Default::default()
+    });
+    assert_eq!(materials.validate(), Ok(()));
+    let sandbox = react(materials, reagent);
+    assert_eq!(sandbox.material_at(2, 0), Some(unobtainium));
+}
+
+/// A solid that always turns the water it touches into `product`.
+fn reagent(product: &str) -> ParticleDefinition {
+    ParticleDefinition {
+        name: "Reagent".into(),
+        movement_type: MovementType::Solid,
+        reactions: vec![ReactionDefinition {
+            with: "Water".into(),
+            chance: 1.0,
+            becomes: Product::Unchanged,
+            other_becomes: Product::Material(product.into()),
+            byproduct: None,
+        }],
+        ..Default::default()
+    }
+}
+
+/// Puts a particle of water on the floor between the reagent and stone, and steps the sandbox
+/// once.
+fn react(materials: MaterialRegistry, reagent: MaterialId) -> Sandbox {
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    sandbox.set_materials(materials);
+    for (x, material) in [(1, reagent), (2, MaterialId::WATER), (3, MaterialId::STONE)] {
+        let particle = sandbox.create_particle(material, x, 0);
+        sandbox.set(x, 0, particle);
+    }
+
+    step_sandbox(&mut sandbox, &SandboxConfig::default());
+    sandbox
+}
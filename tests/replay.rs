@@ -0,0 +1,116 @@
+//! Records placer inputs into a [`Replay`] and plays them back, checking that the replay
+//! follows the recording and that broken replays are rejected.
+
+use bevy::prelude::Entity;
+use bevy_falling_sand::{
+    sandbox::{
+        history::EditHistory,
+        material::{MaterialId, MaterialRegistry},
+        particle_definition::ParticleDefinition,
+        replay::{InputRecorder, PlacerInput, Replay, ReplayError, REPLAY_MAGIC, REPLAY_VERSION},
+        sandbox::Sandbox,
+        simulation::{step_sandbox, SweepStrategy},
+    },
+    SandboxConfig,
+};
+
+const SWEEP: SweepStrategy = SweepStrategy::Alternating;
+
+#[test]
+fn replay_follows_the_recording() {
+    let (sandbox, bytes) = record();
+
+    let replay = Replay::load_from(&bytes[..]).unwrap();
+    let replayed = replay.run(&MaterialRegistry::default()).unwrap();
+
+    assert_eq!(replay.ticks(), 120);
+    assert_eq!(replayed.checksum(), sandbox.checksum());
+}
+
+#[test]
+fn replay_with_other_materials_desyncs() {
+    let (_, bytes) = record();
+
+    // Sand that sinks through everything and doesn't pile up
+    let mut materials = MaterialRegistry::default();
+    materials.register(ParticleDefinition {
+        name: "Sand".into(),
+        ..Default::default()
+    });
+
+    let replay = Replay::load_from(&bytes[..]).unwrap();
+    assert!(matches!(
+        replay.run(&materials),
+        Err(ReplayError::Desync { .. })
+    ));
+}
+
+#[test]
+fn broken_replays_are_rejected() {
+    let (_, bytes) = record();
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    let mut bad_version = bytes.clone();
+    bad_version[4..6].copy_from_slice(&(REPLAY_VERSION + 1).to_le_bytes());
+    let mut corrupted = bytes.clone();
+    corrupted[REPLAY_MAGIC.len() + 2] ^= 0xff;
+    let truncated = &bytes[..bytes.len() / 2];
+
+    for broken in [&bad_magic[..], &bad_version, &corrupted, truncated] {
+        assert!(Replay::load_from(broken).is_err());
+    }
+}
+
+/// Records a few strokes of the brush along with an undo, returning the sandbox once the
+/// recording stopped and the saved replay.
+fn record() -> (Sandbox, Vec<u8>) {
+    let entity = Entity::from_raw(0);
+    let config = SandboxConfig {
+        x_chunks: 8,
+        y_chunks: 8,
+        sweep: SWEEP,
+        ..Default::default()
+    };
+    let mut sandbox = Sandbox::new(
+        config.x_chunks,
+        config.y_chunks,
+        config.chunk_width,
+        config.chunk_height,
+        config.seed,
+    );
+    let mut history = EditHistory::default();
+    let mut recorder = InputRecorder::start(entity, &mut sandbox, &mut history, SWEEP).unwrap();
+
+    for tick in 0..120 {
+        let inputs = match tick {
+            10..=20 => vec![brush(10 + tick, 40, MaterialId::SAND)],
+            30 => vec![PlacerInput::EndStroke],
+            40..=45 => vec![brush(30, 50, MaterialId::WATER)],
+            50 => vec![PlacerInput::EndStroke, PlacerInput::Undo],
+            60 => vec![PlacerInput::Redo],
+            _ => vec![],
+        };
+        for input in inputs {
+            input.apply(entity, &mut sandbox, &mut history);
+            recorder.record(sandbox.ticks(), input);
+        }
+
+        step_sandbox(&mut sandbox, &config);
+        recorder.record_checksum(&sandbox);
+    }
+
+    let mut bytes = vec![];
+    recorder.finish().save_to(&mut bytes).unwrap();
+    (sandbox, bytes)
+}
+
+fn brush(x: usize, y: usize, material: MaterialId) -> PlacerInput {
+    PlacerInput::Brush {
+        x,
+        y,
+        material,
+        place: true,
+        erase: false,
+    }
+}
@@ -0,0 +1,109 @@
+//! Saves and loads sandboxes, and checks that broken saves are rejected before they can make
+//! loading allocate much.
+
+use std::io::{self, ErrorKind};
+
+use bevy_falling_sand::{
+    sandbox::{
+        material::{MaterialId, MaterialRegistry},
+        sandbox::Sandbox,
+        save::{SaveCompression, SAVE_MAGIC, SAVE_VERSION},
+        simulation::step_sandbox,
+    },
+    SandboxConfig,
+};
+
+#[test]
+fn loaded_sandbox_continues_the_same_way() {
+    let config = SandboxConfig::default();
+    let mut sandbox = Sandbox::new(4, 4, 32, 32, 3);
+    for x in 20..60 {
+        for y in 40..60 {
+            let material = [MaterialId::SAND, MaterialId::WATER][(x + y) % 2];
+            let particle = sandbox.create_particle(material, x, y);
+            sandbox.set(x, y, particle);
+        }
+    }
+    for _ in 0..20 {
+        step_sandbox(&mut sandbox, &config);
+    }
+
+    for compression in [SaveCompression::None, SaveCompression::Zlib] {
+        let mut bytes = vec![];
+        sandbox.save_to(&mut bytes, compression).unwrap();
+        let mut loaded = Sandbox::load_from(&bytes[..], &MaterialRegistry::default()).unwrap();
+        assert_eq!(loaded.checksum(), sandbox.checksum());
+
+        // The saved one continues from the seed too
+        let mut saved = Sandbox::load_from(&bytes[..], &MaterialRegistry::default()).unwrap();
+        for _ in 0..50 {
+            step_sandbox(&mut loaded, &config);
+            step_sandbox(&mut saved, &config);
+        }
+        assert_eq!(loaded.checksum(), saved.checksum());
+    }
+}
+
+#[test]
+fn oversized_dimensions_are_rejected() {
+    // 2^16 x 2^16 chunks of 2^16 x 2^16 cells, and none of their particles
+    let mut bytes = header(SaveCompression::None);
+    for _ in 0..4 {
+        bytes.extend_from_slice(&(1u64 << 16).to_le_bytes());
+    }
+
+    let error = load_error(&bytes);
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(
+        error.to_string().contains("cells a sandbox can have"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn oversized_body_is_rejected() {
+    // A single cell, whose save claims a material name of a terabyte
+    let mut bytes = header(SaveCompression::None);
+    for _ in 0..4 {
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+    }
+    bytes.extend_from_slice(&0u64.to_le_bytes()); // seed
+    bytes.extend_from_slice(&0u64.to_le_bytes()); // ticks
+    bytes.extend_from_slice(&20f32.to_le_bytes()); // ambient temperature
+    bytes.extend_from_slice(&0.1f32.to_le_bytes()); // ambient conductivity
+    bytes.extend_from_slice(&1u64.to_le_bytes()); // material count
+    bytes.extend_from_slice(&(1u64 << 40).to_le_bytes()); // material name length
+
+    let error = load_error(&bytes);
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let mut bytes = vec![];
+    Sandbox::new(1, 1, 8, 8, 0)
+        .save_to(&mut bytes, SaveCompression::None)
+        .unwrap();
+    bytes[4..6].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+
+    let error = load_error(&bytes);
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+fn header(compression: SaveCompression) -> Vec<u8> {
+    let mut bytes = SAVE_MAGIC.to_vec();
+    bytes.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    bytes.push(match compression {
+        SaveCompression::None => 0,
+        SaveCompression::Zlib => 1,
+    });
+    bytes
+}
+
+fn load_error(bytes: &[u8]) -> io::Error {
+    match Sandbox::load_from(bytes, &MaterialRegistry::default()) {
+        Ok(_) => panic!("broken save loaded"),
+        Err(error) => error,
+    }
+}
@@ -0,0 +1,99 @@
+//! Pours water onto a flat floor and checks that it spreads the same way to both sides, so
+//! the sweep order doesn't pull liquids towards one side of the sandbox.
+
+use bevy_falling_sand::{
+    sandbox::{
+        material::MaterialId,
+        sandbox::Sandbox,
+        simulation::{step_sandbox, SweepStrategy},
+    },
+    SandboxConfig,
+};
+
+const STEPS: usize = 200;
+const SEEDS: u64 = 8;
+const COLUMN_WIDTH: usize = 4;
+
+/// Least share of the water a side may have compared to the other, summed over every seed.
+const MIN_SYMMETRY: f32 = 0.9;
+
+#[test]
+fn water_column_spreads_evenly() {
+    let (left, right) = spread_over_seeds(SweepStrategy::Alternating, |sandbox| {
+        sandbox.width() / 2 - COLUMN_WIDTH / 2
+    });
+
+    assert_symmetric(left, right);
+}
+
+#[test]
+fn mirrored_scenes_stay_mirrored() {
+    // Water poured left of the center spreads like its mirror image poured right of it
+    let (left, _) = spread_over_seeds(SweepStrategy::Alternating, |sandbox| {
+        sandbox.width() / 4 - COLUMN_WIDTH / 2
+    });
+    let (_, mirrored_right) = spread_over_seeds(SweepStrategy::Alternating, |sandbox| {
+        sandbox.width() - sandbox.width() / 4 - COLUMN_WIDTH / 2
+    });
+
+    assert_symmetric(left, mirrored_right);
+}
+
+fn assert_symmetric(left: usize, right: usize) {
+    let symmetry = left.min(right) as f32 / left.max(right).max(1) as f32;
+    assert!(
+        symmetry >= MIN_SYMMETRY,
+        "water spread unevenly: {} on the left, {} on the right",
+        left,
+        right
+    );
+}
+
+/// Returns the water left and right of the column poured at `column_x`, summed over every seed.
+fn spread_over_seeds(sweep: SweepStrategy, column_x: impl Fn(&Sandbox) -> usize) -> (usize, usize) {
+    let (mut left, mut right) = (0, 0);
+    for seed in 0..SEEDS {
+        let config = SandboxConfig {
+            x_chunks: 12,
+            y_chunks: 4,
+            seed,
+            sweep,
+            ..Default::default()
+        };
+        let mut sandbox = Sandbox::new(
+            config.x_chunks,
+            config.y_chunks,
+            config.chunk_width,
+            config.chunk_height,
+            config.seed,
+        );
+
+        let start = column_x(&sandbox);
+        for x in start..start + COLUMN_WIDTH {
+            for y in 0..sandbox.height() {
+                let particle = sandbox.create_particle(MaterialId::WATER, x, y);
+                sandbox.set(x, y, particle);
+            }
+        }
+
+        for _ in 0..STEPS {
+            step_sandbox(&mut sandbox, &config);
+        }
+
+        let center = start + COLUMN_WIDTH / 2;
+        for x in 0..sandbox.width() {
+            for y in 0..sandbox.height() {
+                if sandbox.get(x, y).is_none() {
+                    continue;
+                }
+
+                match x < center {
+                    true => left += 1,
+                    false => right += 1,
+                }
+            }
+        }
+    }
+
+    (left, right)
+}
@@ -0,0 +1,57 @@
+//! Lays two sand particles next to each other on the floor and checks which way the water
+//! wicks between them.
+
+use bevy_falling_sand::{
+    sandbox::{material::MaterialId, sandbox::Sandbox, simulation::step_sandbox},
+    SandboxConfig,
+};
+
+const WET: f32 = 0.8;
+const DRY: f32 = 0.2;
+
+#[test]
+fn wetter_particle_on_the_left_wicks_into_the_drier_one() {
+    // The wetter particle is updated first
+    let (wet, dry) = wick_once([WET, DRY]);
+
+    assert_wicked(wet, dry);
+}
+
+#[test]
+fn wetter_particle_on_the_right_wicks_into_the_drier_one() {
+    // The drier particle is updated first, and only wakes the wetter one
+    let (dry, wet) = wick_once([DRY, WET]);
+
+    assert_wicked(wet, dry);
+}
+
+fn assert_wicked(wet: f32, dry: f32) {
+    assert!(wet < WET, "the wetter particle kept its water: {}", wet);
+    assert!(dry > DRY, "the drier particle got no water: {}", dry);
+    assert!(
+        dry < wet,
+        "the drier particle got wetter than the wetter one"
+    );
+}
+
+/// Steps two sand particles with the wetness on the left and right once, returning their
+/// wetness afterwards.
+fn wick_once(wetness: [f32; 2]) -> (f32, f32) {
+    let config = SandboxConfig::default();
+    let mut sandbox = Sandbox::new(1, 1, 8, 8, 0);
+    for (x, wetness) in [(3, wetness[0]), (4, wetness[1])] {
+        let mut particle = sandbox.create_particle(MaterialId::SAND, x, 0);
+        if let Some(particle) = &mut particle {
+            particle.set_wetness(wetness);
+        }
+        sandbox.set(x, 0, particle);
+    }
+
+    step_sandbox(&mut sandbox, &config);
+
+    let wetness = |x| {
+        let particle = sandbox.get(x, 0).expect("Sand on the floor stays put");
+        particle.absorbent.expect("Sand gets wet").wetness
+    };
+    (wetness(3), wetness(4))
+}